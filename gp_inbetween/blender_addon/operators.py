@@ -45,9 +45,8 @@ def write_config_file(config_path: Path, api_key: str, threshold: float):
 backend = "replicate"
 endpoint = "http://localhost:8000/generate"
 api_key = "{api_key}"
-replicate_model = "fofr/tooncrafter:0d5c6b3a4e0d6b8a9b8e7d6c5b4a3f2e1d0c9b8a"
 style_strength = 0.8
-timeout_secs = 180
+total_timeout_secs = 180
 
 [preprocessing]
 cleanup_enabled = true
@@ -210,10 +209,18 @@ class GPAI_OT_GenerateInbetweens(bpy.types.Operator):
                     frame_a_num, frame_b_num, len(generated_pngs)
                 )
 
+                near_duplicates = metadata.get("near_duplicate", [])
+                skipped = 0
+
                 for i, (png_path, frame_num) in enumerate(zip(generated_pngs, frame_positions)):
                     confidence = metadata.get("confidence_scores", [0.0] * len(generated_pngs))[i]
                     auto_accept = metadata.get("auto_accept", [False] * len(generated_pngs))[i]
 
+                    # The model copied a keyframe instead of drawing an inbetween
+                    if i < len(near_duplicates) and near_duplicates[i]:
+                        skipped += 1
+                        continue
+
                     self.import_png_to_gp_frame(context, gp_obj, png_path, frame_num)
 
                     if auto_accept:
@@ -223,10 +230,10 @@ class GPAI_OT_GenerateInbetweens(bpy.types.Operator):
                 context.scene.gpai.last_motion_type = metadata.get("motion_type", "unknown")
                 context.scene.gpai.character_name = self.character
 
-                self.report(
-                    {'INFO'},
-                    f"Generated {len(generated_pngs)} frames between {frame_a_num} and {frame_b_num}"
-                )
+                message = f"Generated {len(generated_pngs)} frames between {frame_a_num} and {frame_b_num}"
+                if skipped:
+                    message += f" ({skipped} skipped as copies of a keyframe)"
+                self.report({'INFO'}, message)
                 return {'FINISHED'}
 
         except subprocess.TimeoutExpired:
@@ -0,0 +1,140 @@
+//! Animated WebP previews for `--format animated-webp`.
+//!
+//! `image-webp` only writes still images, so each frame is encoded losslessly on its own
+//! and its VP8L bitstream is wrapped in the animation chunks (VP8X, ANIM, ANMF) here.
+
+use anyhow::{Context, Result};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use image_webp::{ColorType, WebPEncoder};
+use std::io::Write;
+use std::path::Path;
+
+/// VP8X flags: the file holds an animation, and frames carry alpha
+const ANIMATION: u8 = 1 << 1;
+const ALPHA: u8 = 1 << 4;
+/// ANMF flag: replace the canvas instead of compositing over the previous frame, so
+/// transparent areas stay transparent
+const NO_BLEND: u8 = 1 << 1;
+
+/// Write `frames` as a looping animated WebP at `fps`, keeping alpha.
+///
+/// The canvas takes the first frame's size; any frame of another size is scaled to fit.
+pub fn write_animated_webp(frames: &[&DynamicImage], path: &Path, fps: u32) -> Result<()> {
+    let first = frames
+        .first()
+        .context("an animation needs at least one frame")?;
+    let (width, height) = (first.width(), first.height());
+
+    let mut body = Vec::new();
+    body.extend_from_slice(b"WEBP");
+    let mut vp8x = vec![ANIMATION | ALPHA, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(width - 1));
+    vp8x.extend_from_slice(&u24(height - 1));
+    write_chunk(&mut body, *b"VP8X", &vp8x)?;
+    // Transparent background, loop forever
+    write_chunk(&mut body, *b"ANIM", &[0, 0, 0, 0, 0, 0])?;
+
+    for (i, frame) in frames.iter().enumerate() {
+        let rgba = if (frame.width(), frame.height()) == (width, height) {
+            frame.to_rgba8()
+        } else {
+            frame
+                .resize_exact(width, height, FilterType::Triangle)
+                .to_rgba8()
+        };
+
+        let mut anmf = Vec::new();
+        anmf.extend_from_slice(&u24(0)); // x offset
+        anmf.extend_from_slice(&u24(0)); // y offset
+        anmf.extend_from_slice(&u24(width - 1));
+        anmf.extend_from_slice(&u24(height - 1));
+        anmf.extend_from_slice(&u24(frame_duration_ms(i, fps)));
+        anmf.push(NO_BLEND);
+        write_chunk(
+            &mut anmf,
+            *b"VP8L",
+            &encode_lossless(rgba.as_raw(), width, height)?,
+        )?;
+        write_chunk(&mut body, *b"ANMF", &anmf)?;
+    }
+
+    let mut file = Vec::with_capacity(body.len() + 8);
+    file.extend_from_slice(b"RIFF");
+    file.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    file.extend_from_slice(&body);
+    std::fs::write(path, file).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Display time of frame `i`, rounded so the whole loop still runs at exactly `fps`
+fn frame_duration_ms(i: usize, fps: u32) -> u32 {
+    let end = |frame: usize| ((frame as u64 * 1000 + u64::from(fps) / 2) / u64::from(fps)) as u32;
+    end(i + 1) - end(i)
+}
+
+/// The VP8L bitstream of one frame, lifted out of the simple-format file `image-webp`
+/// writes (`RIFF` size `WEBP` `VP8L` size data)
+fn encode_lossless(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    let mut still = Vec::new();
+    WebPEncoder::new(&mut still).encode(rgba, width, height, ColorType::Rgba8)?;
+    anyhow::ensure!(
+        still.get(12..16) == Some(b"VP8L".as_slice()),
+        "unexpected WebP encoder output"
+    );
+    let size = u32::from_le_bytes([still[16], still[17], still[18], still[19]]) as usize;
+    Ok(still[20..20 + size].to_vec())
+}
+
+fn write_chunk(out: &mut Vec<u8>, fourcc: [u8; 4], data: &[u8]) -> Result<()> {
+    out.write_all(&fourcc)?;
+    out.write_all(&(data.len() as u32).to_le_bytes())?;
+    out.write_all(data)?;
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+    Ok(())
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let [a, b, c, _] = value.to_le_bytes();
+    [a, b, c]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use image_webp::WebPDecoder;
+
+    #[test]
+    fn test_animated_webp_keeps_frames_and_alpha() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("animation.webp");
+        let frames: Vec<DynamicImage> = (0..5u8)
+            .map(|i| {
+                DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 12, |x, _| {
+                    if x == u32::from(i) * 3 {
+                        Rgba([10, 10, 30, 255])
+                    } else {
+                        Rgba([0, 0, 0, 0])
+                    }
+                }))
+            })
+            .collect();
+        let refs: Vec<&DynamicImage> = frames.iter().collect();
+        write_animated_webp(&refs, &path, 12).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let mut decoder = WebPDecoder::new(std::io::BufReader::new(file)).unwrap();
+        assert!(decoder.is_animated());
+        assert!(decoder.has_alpha());
+        assert_eq!(decoder.num_frames(), 5);
+        assert_eq!(decoder.dimensions(), (16, 12));
+        assert_eq!(decoder.loop_duration(), 417);
+
+        let mut buffer = vec![0; decoder.output_buffer_size().unwrap()];
+        decoder.read_frame(&mut buffer).unwrap();
+        let first_frame = RgbaImage::from_raw(16, 12, buffer).unwrap();
+        assert_eq!(first_frame, frames[0].to_rgba8());
+    }
+}
@@ -0,0 +1,198 @@
+//! Confidence badges for `--badge`.
+//!
+//! A badge is a dark box in one corner of the frame with the score printed in a 3x5
+//! pixel font above a bar filled to the score, green for auto-accepted frames, red for
+//! rejected or unscored ones and yellow for the rest. It goes in the corner with the
+//! least drawing under it, so on frames with empty margins it covers nothing.
+
+use gp_core::ScoredFrame;
+use image::{DynamicImage, GenericImage, GenericImageView, Rgba};
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+/// "0.00" is four glyphs with a gap between each
+const TEXT_WIDTH: u32 = 4 * (GLYPH_WIDTH + 1) - 1;
+const BAR_HEIGHT: u32 = 2;
+/// Badge size in font pixels: text and bar with a one-pixel border around and between
+const BADGE_WIDTH: u32 = TEXT_WIDTH + 2;
+const BADGE_HEIGHT: u32 = GLYPH_HEIGHT + BAR_HEIGHT + 3;
+/// Frame size per font pixel, so the badge stays readable on large frames
+const FRAME_PIXELS_PER_UNIT: u32 = 160;
+
+const BACKGROUND: Rgba<u8> = Rgba([24, 24, 24, 255]);
+const TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const BAR_TRACK: Rgba<u8> = Rgba([70, 70, 70, 255]);
+const ACCEPT: Rgba<u8> = Rgba([46, 160, 67, 255]);
+const REVIEW: Rgba<u8> = Rgba([230, 180, 30, 255]);
+const REJECT: Rgba<u8> = Rgba([218, 54, 51, 255]);
+
+/// Where a badge is drawn, in frame pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BadgeRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A copy of the frame with its confidence badge drawn in, in the frame's own pixel
+/// format. Frames too small to hold a badge are returned unchanged.
+pub fn draw_badge(frame: &ScoredFrame) -> DynamicImage {
+    let mut badged = frame.frame.clone();
+    let Some(rect) = badge_rect(&frame.frame) else {
+        return badged;
+    };
+    let unit = rect.width / BADGE_WIDTH;
+    let mut fill = |ux: u32, uy: u32, width: u32, height: u32, color: Rgba<u8>| {
+        for y in rect.y + uy * unit..rect.y + (uy + height) * unit {
+            for x in rect.x + ux * unit..rect.x + (ux + width) * unit {
+                badged.put_pixel(x, y, color);
+            }
+        }
+    };
+
+    fill(0, 0, BADGE_WIDTH, BADGE_HEIGHT, BACKGROUND);
+    let score = frame.score.clamp(0.0, 1.0);
+    for (i, c) in format!("{score:.2}").chars().enumerate() {
+        let left = 1 + i as u32 * (GLYPH_WIDTH + 1);
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    fill(left + column, 1 + row as u32, 1, 1, TEXT);
+                }
+            }
+        }
+    }
+
+    let bar_top = GLYPH_HEIGHT + 2;
+    fill(1, bar_top, TEXT_WIDTH, BAR_HEIGHT, BAR_TRACK);
+    let filled = (score * TEXT_WIDTH as f32).round() as u32;
+    fill(1, bar_top, filled, BAR_HEIGHT, status_color(frame));
+    badged
+}
+
+/// The badge's place: whichever corner has the least drawing under it, top-left first
+/// on ties
+pub fn badge_rect(frame: &DynamicImage) -> Option<BadgeRect> {
+    let (width, height) = frame.dimensions();
+    let unit = (width.min(height) / FRAME_PIXELS_PER_UNIT).max(1);
+    let (badge_width, badge_height) = (BADGE_WIDTH * unit, BADGE_HEIGHT * unit);
+    if badge_width > width || badge_height > height {
+        return None;
+    }
+
+    let (right, bottom) = (width - badge_width, height - badge_height);
+    let corners = [(0, 0), (right, 0), (0, bottom), (right, bottom)];
+    // Transparency is the background where there is any; opaque frames are drawn on
+    // paper, taken to be the color most of the frame's corners share
+    let has_alpha = frame.color().has_alpha();
+    let paper = [
+        (0, 0),
+        (width - 1, 0),
+        (0, height - 1),
+        (width - 1, height - 1),
+    ]
+    .map(|(x, y)| frame.get_pixel(x, y));
+    let paper = *paper
+        .iter()
+        .max_by_key(|color| paper.iter().filter(|other| other == color).count())
+        .unwrap_or(&paper[0]);
+    let is_drawing = |pixel: Rgba<u8>| {
+        if has_alpha {
+            pixel[3] > 0
+        } else {
+            pixel != paper
+        }
+    };
+
+    corners
+        .into_iter()
+        .map(|(x, y)| BadgeRect {
+            x,
+            y,
+            width: badge_width,
+            height: badge_height,
+        })
+        .min_by_key(|rect| {
+            (rect.y..rect.y + rect.height)
+                .flat_map(|y| (rect.x..rect.x + rect.width).map(move |x| (x, y)))
+                .filter(|&(x, y)| is_drawing(frame.get_pixel(x, y)))
+                .count()
+        })
+}
+
+fn status_color(frame: &ScoredFrame) -> Rgba<u8> {
+    if frame.scoring_error.is_some() || frame.auto_reject {
+        REJECT
+    } else if frame.auto_accept {
+        ACCEPT
+    } else {
+        REVIEW
+    }
+}
+
+/// Rows of a 3x5 glyph, most significant bit leftmost
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        _ => [0; 5],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_badge_only_changes_its_corner() {
+        // A drawing in the top-left corner of a transparent frame
+        let drawing = DynamicImage::ImageRgba8(RgbaImage::from_fn(200, 120, |x, y| {
+            if x < 60 && y < 40 {
+                Rgba([10, 10, 10, 255])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        }));
+        let frame = ScoredFrame {
+            frame: drawing.clone(),
+            score: 0.87,
+            auto_accept: true,
+            auto_reject: false,
+            penalties: Vec::new(),
+            near_duplicate: false,
+            repeats_previous: false,
+            aspect_distorted: false,
+            scoring_error: None,
+            smoothed_score: None,
+            synthesized: false,
+        };
+
+        let rect = badge_rect(&drawing).unwrap();
+        assert_eq!((rect.x, rect.y), (200 - rect.width, 0));
+        let badged = draw_badge(&frame);
+        assert_eq!(badged.color(), drawing.color());
+
+        let mut changed = 0;
+        for (x, y, pixel) in badged.pixels() {
+            if pixel != drawing.get_pixel(x, y) {
+                let inside = (rect.x..rect.x + rect.width).contains(&x)
+                    && (rect.y..rect.y + rect.height).contains(&y);
+                assert!(inside, "({x}, {y}) changed outside the badge");
+                changed += 1;
+            }
+        }
+        assert!(changed > 0);
+        assert_eq!(badged.get_pixel(rect.x, rect.y), BACKGROUND);
+    }
+}
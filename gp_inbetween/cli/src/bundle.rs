@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use image::{DynamicImage, ImageOutputFormat};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Package a finished generation into one zip for a reviewer, laid out as
+///
+/// ```text
+/// README.txt             each frame's score and status
+/// metadata.json
+/// keyframes/frame_a.png
+/// keyframes/frame_b.png
+/// frames/<frame files>   as named in the output directory
+/// ```
+///
+/// Frames are flattened into `frames/`, as `rescore` expects, so two frames in
+/// different subdirectories with the same file name are an error rather than one
+/// silently replacing the other.
+pub fn write_bundle(
+    bundle_path: &Path,
+    readme: &str,
+    metadata_path: &Path,
+    keyframes: (&DynamicImage, &DynamicImage),
+    frame_paths: &[PathBuf],
+) -> Result<()> {
+    let mut frame_names = Vec::with_capacity(frame_paths.len());
+    let mut seen = HashSet::new();
+    for path in frame_paths {
+        let name = path
+            .file_name()
+            .with_context(|| format!("Frame path {} has no file name", path.display()))?;
+        if !seen.insert(name) {
+            anyhow::bail!(
+                "More than one frame is named {}; use an output template that gives each \
+                 frame its own file name to bundle them",
+                name.to_string_lossy()
+            );
+        }
+        frame_names.push(format!("frames/{}", name.to_string_lossy()));
+    }
+
+    let file = File::create(bundle_path)
+        .with_context(|| format!("Failed to create bundle {}", bundle_path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    // Images are already compressed; only the text is worth deflating
+    let text = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let image = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+    zip.start_file("README.txt", text)?;
+    zip.write_all(readme.as_bytes())?;
+
+    zip.start_file("metadata.json", text)?;
+    zip.write_all(&read(metadata_path)?)?;
+
+    for (name, keyframe) in [("frame_a", keyframes.0), ("frame_b", keyframes.1)] {
+        let mut png = Vec::new();
+        keyframe.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+        zip.start_file(format!("keyframes/{name}.png"), image)?;
+        zip.write_all(&png)?;
+    }
+
+    for (path, name) in frame_paths.iter().zip(frame_names) {
+        zip.start_file(name, image)?;
+        zip.write_all(&read(path)?)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_bundle_layout() {
+        let dir = tempdir().unwrap();
+        let metadata = dir.path().join("metadata.json");
+        std::fs::write(&metadata, "{}").unwrap();
+        let frames: Vec<_> = (0..2)
+            .map(|i| {
+                let path = dir.path().join(format!("frame_{i:03}.png"));
+                DynamicImage::new_rgba8(2, 2).save(&path).unwrap();
+                path
+            })
+            .collect();
+        let keyframe = DynamicImage::new_rgba8(2, 2);
+
+        let bundle = dir.path().join("bundle.zip");
+        write_bundle(
+            &bundle,
+            "frames/frame_000.png: 0.90 (auto-accept)\n",
+            &metadata,
+            (&keyframe, &keyframe),
+            &frames,
+        )
+        .unwrap();
+
+        let archive = zip::ZipArchive::new(File::open(&bundle).unwrap()).unwrap();
+        let mut names: Vec<_> = archive.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec![
+                "README.txt",
+                "frames/frame_000.png",
+                "frames/frame_001.png",
+                "keyframes/frame_a.png",
+                "keyframes/frame_b.png",
+                "metadata.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bundle_rejects_colliding_frame_names() {
+        let dir = tempdir().unwrap();
+        let metadata = dir.path().join("metadata.json");
+        std::fs::write(&metadata, "{}").unwrap();
+        // The same file name in two subdirectories of the output directory
+        let frames: Vec<_> = ["hero", "villain"]
+            .iter()
+            .map(|character| {
+                let path = dir.path().join(character).join("frame.png");
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                DynamicImage::new_rgba8(2, 2).save(&path).unwrap();
+                path
+            })
+            .collect();
+        let keyframe = DynamicImage::new_rgba8(2, 2);
+
+        let bundle = dir.path().join("bundle.zip");
+        let err =
+            write_bundle(&bundle, "", &metadata, (&keyframe, &keyframe), &frames).unwrap_err();
+        assert!(err.to_string().contains("frame.png"), "{err}");
+        assert!(!bundle.exists());
+    }
+}
@@ -0,0 +1,521 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, Rgb, RgbImage};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Channel depth for PNG output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum BitDepth {
+    #[default]
+    #[value(name = "8")]
+    Eight,
+    #[value(name = "16")]
+    Sixteen,
+}
+
+/// File format for saved frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum FrameFormat {
+    #[default]
+    Png,
+    /// Linear 32-bit float `OpenEXR` (requires the `exr` feature)
+    Exr,
+    /// Lossy 8-bit JPEG, always flattened onto the background color
+    Jpeg,
+    /// PNG frames, plus `animation.webp` of keyframe A, the inbetweens and keyframe B
+    /// with alpha kept (requires the `webp` feature)
+    AnimatedWebp,
+}
+
+impl FrameFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png | Self::AnimatedWebp => "png",
+            Self::Exr => "exr",
+            Self::Jpeg => "jpg",
+        }
+    }
+}
+
+/// zlib effort for PNG output; PNGs stay lossless at every level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PngCompression {
+    /// Quickest to write, largest files
+    #[default]
+    Fast,
+    Balanced,
+    /// Smallest files, slowest to write
+    Best,
+}
+
+impl From<PngCompression> for png::Compression {
+    fn from(compression: PngCompression) -> Self {
+        match compression {
+            PngCompression::Fast => Self::Fast,
+            PngCompression::Balanced => Self::Default,
+            PngCompression::Best => Self::Best,
+        }
+    }
+}
+
+/// Channel layout for saved frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputColor {
+    /// Keep whatever the model returned
+    #[default]
+    Auto,
+    Rgba,
+    /// Flatten onto the background color
+    Rgb,
+}
+
+/// How frames are encoded on disk
+#[derive(Debug, Clone, Copy)]
+pub struct FrameEncoding {
+    pub format: FrameFormat,
+    pub bit_depth: BitDepth,
+    pub color: OutputColor,
+    /// Color transparent areas become when flattening to RGB
+    pub background: [u8; 3],
+    pub png_compression: PngCompression,
+    /// JPEG quality, 1-100
+    pub jpeg_quality: u8,
+    /// Palette quality (1-100) for `--optimize`; `None` writes PNGs as they are
+    pub optimize: Option<u8>,
+}
+
+impl Default for FrameEncoding {
+    fn default() -> Self {
+        Self {
+            format: FrameFormat::default(),
+            bit_depth: BitDepth::default(),
+            color: OutputColor::default(),
+            background: [255, 255, 255],
+            png_compression: PngCompression::default(),
+            jpeg_quality: 90,
+            optimize: None,
+        }
+    }
+}
+
+impl FrameEncoding {
+    /// Reject combinations `save_frame` can't write, so they fail before any API call
+    /// instead of at the first saved frame
+    pub fn validate(&self) -> Result<()> {
+        if self.format == FrameFormat::Exr && !cfg!(feature = "exr") {
+            anyhow::bail!(
+                "EXR output is not available in this build (rebuild with --features exr)"
+            );
+        }
+        if self.format == FrameFormat::AnimatedWebp && !cfg!(feature = "webp") {
+            anyhow::bail!(
+                "Animated WebP output is not available in this build (rebuild with --features webp)"
+            );
+        }
+        if self.format == FrameFormat::Jpeg && self.bit_depth == BitDepth::Sixteen {
+            anyhow::bail!("JPEG output is 8-bit only; drop --bit-depth 16 or use PNG");
+        }
+        if self.optimize.is_some() {
+            if !cfg!(feature = "optimize") {
+                anyhow::bail!(
+                    "--optimize is not available in this build (rebuild with --features optimize)"
+                );
+            }
+            if self.bit_depth == BitDepth::Sixteen {
+                anyhow::bail!("--optimize writes 8-bit palette PNGs; drop --bit-depth 16");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Save a frame, converting to the requested format, channels and depth. `text` is
+/// written into PNG output as `(keyword, value)` chunks; JPEG and EXR frames go without.
+///
+/// Scoring happens on the 8-bit frame before this point; only the written file changes.
+pub fn save_frame(
+    frame: &DynamicImage,
+    path: &Path,
+    encoding: FrameEncoding,
+    text: &[(String, String)],
+) -> Result<()> {
+    let converted = match encoding.color {
+        OutputColor::Auto => None,
+        OutputColor::Rgba => Some(DynamicImage::ImageRgba8(frame.to_rgba8())),
+        OutputColor::Rgb => Some(DynamicImage::ImageRgb8(flatten(frame, encoding.background))),
+    };
+    let frame = converted.as_ref().unwrap_or(frame);
+
+    match (encoding.format, encoding.bit_depth) {
+        (FrameFormat::Png | FrameFormat::AnimatedWebp, BitDepth::Eight) => {
+            match encoding.optimize {
+                Some(quality) => save_optimized(frame, path, quality, text)?,
+                None => save_png(frame, path, encoding.png_compression, text)?,
+            }
+        }
+        (FrameFormat::Png | FrameFormat::AnimatedWebp, BitDepth::Sixteen)
+            if encoding.optimize.is_some() =>
+        {
+            anyhow::bail!("--optimize writes 8-bit palette PNGs; drop --bit-depth 16")
+        }
+        (FrameFormat::Png | FrameFormat::AnimatedWebp, BitDepth::Sixteen) => {
+            let widened = if frame.color().has_alpha() || encoding.color == OutputColor::Auto {
+                DynamicImage::ImageRgba16(frame.to_rgba16())
+            } else {
+                DynamicImage::ImageRgb16(frame.to_rgb16())
+            };
+            save_png(&widened, path, encoding.png_compression, text)?;
+        }
+        (FrameFormat::Exr, _) => save_exr(frame, path)?,
+        (FrameFormat::Jpeg, BitDepth::Sixteen) => {
+            anyhow::bail!("JPEG output is 8-bit only; drop --bit-depth 16 or use PNG")
+        }
+        (FrameFormat::Jpeg, BitDepth::Eight) => {
+            let flattened = DynamicImage::ImageRgb8(flatten(frame, encoding.background));
+            let writer = BufWriter::new(File::create(path)?);
+            flattened
+                .write_with_encoder(JpegEncoder::new_with_quality(writer, encoding.jpeg_quality))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn save_png(
+    frame: &DynamicImage,
+    path: &Path,
+    compression: PngCompression,
+    text: &[(String, String)],
+) -> Result<()> {
+    encode_png(
+        BufWriter::new(File::create(path)?),
+        frame,
+        compression.into(),
+        text,
+    )
+}
+
+/// Encode with the `png` crate directly so text chunks can go in with the pixels,
+/// filtering the way `image`'s adaptive setting does
+pub(crate) fn encode_png(
+    writer: impl Write,
+    frame: &DynamicImage,
+    compression: png::Compression,
+    text: &[(String, String)],
+) -> Result<()> {
+    use png::{BitDepth, ColorType};
+
+    // PNG stores 16-bit samples big-endian
+    let big_endian = |samples: &[u16]| -> Cow<[u8]> {
+        samples
+            .iter()
+            .flat_map(|sample| sample.to_be_bytes())
+            .collect()
+    };
+    let (color, depth, data) = match frame {
+        DynamicImage::ImageLuma8(image) => {
+            (ColorType::Grayscale, BitDepth::Eight, image.as_raw().into())
+        }
+        DynamicImage::ImageLumaA8(image) => (
+            ColorType::GrayscaleAlpha,
+            BitDepth::Eight,
+            image.as_raw().into(),
+        ),
+        DynamicImage::ImageRgb8(image) => (ColorType::Rgb, BitDepth::Eight, image.as_raw().into()),
+        DynamicImage::ImageRgba8(image) => {
+            (ColorType::Rgba, BitDepth::Eight, image.as_raw().into())
+        }
+        DynamicImage::ImageLuma16(image) => (
+            ColorType::Grayscale,
+            BitDepth::Sixteen,
+            big_endian(image.as_raw()),
+        ),
+        DynamicImage::ImageLumaA16(image) => (
+            ColorType::GrayscaleAlpha,
+            BitDepth::Sixteen,
+            big_endian(image.as_raw()),
+        ),
+        DynamicImage::ImageRgb16(image) => (
+            ColorType::Rgb,
+            BitDepth::Sixteen,
+            big_endian(image.as_raw()),
+        ),
+        DynamicImage::ImageRgba16(image) => (
+            ColorType::Rgba,
+            BitDepth::Sixteen,
+            big_endian(image.as_raw()),
+        ),
+        // Float frames have no PNG equivalent
+        _ => (
+            ColorType::Rgba,
+            BitDepth::Eight,
+            frame.to_rgba8().into_raw().into(),
+        ),
+    };
+
+    let mut encoder = png::Encoder::new(writer, frame.width(), frame.height());
+    encoder.set_color(color);
+    encoder.set_depth(depth);
+    encoder.set_compression(compression);
+    encoder.set_filter(png::FilterType::Sub);
+    encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+    add_text(&mut encoder, text)?;
+    encoder.write_header()?.write_image_data(&data)?;
+    Ok(())
+}
+
+/// Queue text chunks on an encoder: tEXt for plain ASCII, iTXt (UTF-8) otherwise
+pub(crate) fn add_text<W: Write>(
+    encoder: &mut png::Encoder<W>,
+    text: &[(String, String)],
+) -> Result<()> {
+    for (keyword, value) in text {
+        if value.is_ascii() {
+            encoder.add_text_chunk(keyword.clone(), value.clone())?;
+        } else {
+            encoder.add_itxt_chunk(keyword.clone(), value.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Composite a frame over a solid background, dropping alpha
+fn flatten(frame: &DynamicImage, background: [u8; 3]) -> RgbImage {
+    let rgba = frame.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let alpha = u32::from(a);
+        let blend = |c: u8, bg: u8| {
+            ((u32::from(c) * alpha + u32::from(bg) * (255 - alpha) + 127) / 255) as u8
+        };
+        Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    })
+}
+
+/// Parse a `#rrggbb` (or `rrggbb`) color for `--background`
+pub fn parse_hex_color(value: &str) -> Result<[u8; 3], String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("expected a color like #ffffff, got '{value}'"));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.to_string());
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(feature = "optimize")]
+fn save_optimized(
+    frame: &DynamicImage,
+    path: &Path,
+    quality: u8,
+    text: &[(String, String)],
+) -> Result<()> {
+    crate::palette::save_optimized(frame, path, quality, text)
+}
+
+#[cfg(not(feature = "optimize"))]
+fn save_optimized(
+    _frame: &DynamicImage,
+    _path: &Path,
+    _quality: u8,
+    _text: &[(String, String)],
+) -> Result<()> {
+    anyhow::bail!("--optimize is not available in this build (rebuild with --features optimize)")
+}
+
+#[cfg(feature = "exr")]
+fn save_exr(frame: &DynamicImage, path: &Path) -> Result<()> {
+    // EXR is expected to hold linear light, so undo the sRGB transfer curve
+    if frame.color().has_alpha() {
+        let mut buffer = frame.to_rgba32f();
+        for pixel in buffer.pixels_mut() {
+            for channel in &mut pixel.0[..3] {
+                *channel = srgb_to_linear(*channel);
+            }
+        }
+        DynamicImage::ImageRgba32F(buffer).save(path)?;
+    } else {
+        let mut buffer = frame.to_rgb32f();
+        for pixel in buffer.pixels_mut() {
+            for channel in &mut pixel.0 {
+                *channel = srgb_to_linear(*channel);
+            }
+        }
+        DynamicImage::ImageRgb32F(buffer).save(path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "exr"))]
+fn save_exr(_frame: &DynamicImage, _path: &Path) -> Result<()> {
+    anyhow::bail!("EXR output is not available in this build (rebuild with --features exr)")
+}
+
+#[cfg(feature = "exr")]
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ColorType;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_save_16_bit_png() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("0000.png");
+        let frame = DynamicImage::new_rgba8(4, 4);
+
+        let sixteen = FrameEncoding {
+            bit_depth: BitDepth::Sixteen,
+            ..FrameEncoding::default()
+        };
+        save_frame(&frame, &path, sixteen, &[]).unwrap();
+        assert_eq!(image::open(&path).unwrap().color(), ColorType::Rgba16);
+
+        save_frame(&frame, &path, FrameEncoding::default(), &[]).unwrap();
+        assert_eq!(image::open(&path).unwrap().color(), ColorType::Rgba8);
+    }
+
+    #[test]
+    fn test_rgb_output_flattens_onto_background() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("0000.png");
+        // Fully transparent frame
+        let frame = DynamicImage::new_rgba8(4, 4);
+
+        let rgb = FrameEncoding {
+            color: OutputColor::Rgb,
+            background: parse_hex_color("#336699").unwrap(),
+            ..FrameEncoding::default()
+        };
+        save_frame(&frame, &path, rgb, &[]).unwrap();
+
+        let saved = image::open(&path).unwrap();
+        assert_eq!(saved.color(), ColorType::Rgb8);
+        assert_eq!(saved.to_rgb8().get_pixel(0, 0).0, [0x33, 0x66, 0x99]);
+
+        assert!(parse_hex_color("white").is_err());
+    }
+
+    #[test]
+    fn test_unwritable_encodings_rejected_up_front() {
+        assert!(FrameEncoding::default().validate().is_ok());
+
+        let exr = FrameEncoding {
+            format: FrameFormat::Exr,
+            ..FrameEncoding::default()
+        };
+        assert_eq!(exr.validate().is_ok(), cfg!(feature = "exr"));
+        let webp = FrameEncoding {
+            format: FrameFormat::AnimatedWebp,
+            ..FrameEncoding::default()
+        };
+        assert_eq!(webp.validate().is_ok(), cfg!(feature = "webp"));
+
+        let deep_jpeg = FrameEncoding {
+            format: FrameFormat::Jpeg,
+            bit_depth: BitDepth::Sixteen,
+            ..FrameEncoding::default()
+        };
+        assert!(deep_jpeg
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("8-bit only"));
+
+        let optimized = FrameEncoding {
+            optimize: Some(90),
+            ..FrameEncoding::default()
+        };
+        assert_eq!(optimized.validate().is_ok(), cfg!(feature = "optimize"));
+        let deep_optimized = FrameEncoding {
+            bit_depth: BitDepth::Sixteen,
+            ..optimized
+        };
+        assert!(deep_optimized.validate().is_err());
+    }
+
+    #[test]
+    fn test_png_compression_levels() {
+        let dir = tempdir().unwrap();
+        let frame = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([(x * 4) as u8, ((x * y) % 7 * 30) as u8, (y * 4) as u8, 255])
+        }));
+
+        let size = |compression: PngCompression| {
+            let path = dir.path().join(format!("{compression:?}.png"));
+            let encoding = FrameEncoding {
+                png_compression: compression,
+                ..FrameEncoding::default()
+            };
+            save_frame(&frame, &path, encoding, &[]).unwrap();
+            assert_eq!(image::open(&path).unwrap(), frame);
+            std::fs::metadata(&path).unwrap().len()
+        };
+        assert!(size(PngCompression::Fast) > size(PngCompression::Best));
+    }
+
+    #[cfg(feature = "optimize")]
+    #[test]
+    fn test_optimized_png_is_smaller_and_near_identical() {
+        let dir = tempdir().unwrap();
+        // An antialiased ring with a shaded fill, over a transparent background that still
+        // carries leftover color the way model output often does
+        let frame = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(96, 96, |x, y| {
+            let distance = (x as f32 - 48.0).hypot(y as f32 - 48.0);
+            let stroke = (1.0 - (distance - 30.0).abs() / 2.0).clamp(0.0, 1.0);
+            if stroke > 0.0 {
+                image::Rgba([20, 20, 40, (stroke * 255.0).round() as u8])
+            } else if distance < 30.0 {
+                image::Rgba([(x * 2) as u8, 120, (y * 2) as u8, 255])
+            } else {
+                let noise = (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)).to_le_bytes();
+                image::Rgba([noise[1], noise[2], noise[3], 0])
+            }
+        }));
+
+        let plain = dir.path().join("plain.png");
+        let optimized = dir.path().join("optimized.png");
+        save_frame(&frame, &plain, FrameEncoding::default(), &[]).unwrap();
+        let encoding = FrameEncoding {
+            optimize: Some(90),
+            ..FrameEncoding::default()
+        };
+        save_frame(&frame, &optimized, encoding, &[]).unwrap();
+
+        let size = |path: &Path| std::fs::metadata(path).unwrap().len();
+        assert!(size(&optimized) * 2 < size(&plain));
+        // IHDR color type 3: the palette path was taken
+        assert_eq!(std::fs::read(&optimized).unwrap()[25], 3);
+
+        let decoded = image::open(&optimized).unwrap().to_rgba8();
+        for (original, saved) in frame.to_rgba8().pixels().zip(decoded.pixels()) {
+            let visible = |pixel: &image::Rgba<u8>| {
+                let alpha = f32::from(pixel[3]) / 255.0;
+                [0, 1, 2].map(|c| f32::from(pixel[c]) * alpha)
+            };
+            let drift = visible(original)
+                .iter()
+                .zip(visible(saved))
+                .map(|(a, b)| (a - b).abs())
+                .fold(f32::from(original[3].abs_diff(saved[3])), f32::max);
+            assert!(drift <= 5.0, "{original:?} became {saved:?}");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Ctrl-C handling for `generate`.
+//!
+//! The first Ctrl-C only asks the run to stop: a Replicate prediction in flight is
+//! canceled and no further retry or candidate starts, but frames already generated are
+//! still saved. A Ctrl-C while saving stops after the frame being written, leaving a
+//! metadata.json that covers the frames saved so far and is marked incomplete. A
+//! second Ctrl-C exits at once, deleting the frame it cut off.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Exit status after a Ctrl-C, as shells report a process stopped by SIGINT
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+/// Shared between the Ctrl-C handler and the run it interrupts. The default one is
+/// never triggered.
+#[derive(Clone, Default)]
+pub struct Interrupt {
+    requested: Arc<AtomicBool>,
+    /// The frame file being written, if any
+    writing: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl Interrupt {
+    /// Route Ctrl-C to a new `Interrupt`. A process can only install one handler.
+    pub fn install() -> Result<Self> {
+        let interrupt = Self::default();
+        let handler = interrupt.clone();
+        ctrlc::set_handler(move || handler.on_ctrl_c())
+            .context("Failed to install the Ctrl-C handler")?;
+        Ok(interrupt)
+    }
+
+    fn on_ctrl_c(&self) {
+        if !self.requested.swap(true, Ordering::SeqCst) {
+            eprintln!("Interrupted; stopping after the current step (Ctrl-C again to quit now)");
+            return;
+        }
+        let writing = self
+            .writing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(path) = writing {
+            let _ = std::fs::remove_file(path);
+        }
+        std::process::exit(i32::from(EXIT_INTERRUPTED));
+    }
+
+    /// Whether Ctrl-C has been pressed, clearing it so that the next Ctrl-C stops
+    /// the run again instead of quitting at once
+    pub fn acknowledge(&self) -> bool {
+        self.requested.swap(false, Ordering::SeqCst)
+    }
+
+    /// The flag Ctrl-C sets, for the generator and save loop to poll
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.requested)
+    }
+
+    /// Run `write`, which creates `path`. If it fails, or a second Ctrl-C quits while
+    /// it runs, the partly written file is deleted.
+    pub fn writing<T>(&self, path: &Path, write: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.set_writing(Some(path.to_path_buf()));
+        let result = write();
+        self.set_writing(None);
+        if result.is_err() {
+            let _ = std::fs::remove_file(path);
+        }
+        result
+    }
+
+    fn set_writing(&self, path: Option<PathBuf>) {
+        *self.writing.lock().unwrap_or_else(PoisonError::into_inner) = path;
+    }
+}
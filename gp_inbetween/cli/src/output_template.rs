@@ -0,0 +1,286 @@
+use anyhow::{bail, Result};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Template reproducing the original `0000.png` naming
+pub const DEFAULT_TEMPLATE: &str = "{index:04}.png";
+
+/// Layout reproducing the original `pair_0000` batch folders
+pub const DEFAULT_PAIR_LAYOUT: &str = "{pair}";
+
+/// A parsed `--output-template`, e.g. `shot010_inbetween_{index:03}.png`
+#[derive(Debug, Clone)]
+pub struct OutputTemplate {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Index { width: usize },
+    Character,
+    Motion,
+    Score,
+}
+
+/// Per-frame values substituted into the template
+pub struct FrameContext<'a> {
+    pub index: usize,
+    pub character: Option<&'a str>,
+    pub motion: Option<&'a str>,
+    pub score: f32,
+}
+
+impl OutputTemplate {
+    /// Parse a template, rejecting unknown placeholders and templates without `{index}`
+    pub fn parse(template: &str) -> Result<Self> {
+        let parts = split_placeholders(template, "output template")?
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Literal(text) => Ok(Part::Literal(text)),
+                Piece::Placeholder(placeholder) => Self::parse_placeholder(placeholder),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Without a per-frame index every frame would overwrite the previous one
+        if !parts.iter().any(|part| matches!(part, Part::Index { .. })) {
+            bail!("Output template must contain an {{index}} placeholder: {template}");
+        }
+
+        Ok(Self { parts })
+    }
+
+    fn parse_placeholder(placeholder: &str) -> Result<Part> {
+        let part = match placeholder {
+            "index" => Part::Index { width: 0 },
+            "character" => Part::Character,
+            "motion" => Part::Motion,
+            "score" => Part::Score,
+            _ => {
+                let width = placeholder
+                    .strip_prefix("index:")
+                    .and_then(|spec| spec.strip_prefix('0').unwrap_or(spec).parse().ok());
+                match width {
+                    Some(width) => Part::Index { width },
+                    None => bail!("Unknown output template placeholder: {{{placeholder}}}"),
+                }
+            }
+        };
+
+        Ok(part)
+    }
+
+    /// Whether names depend on generation results (score, auto-detected motion), so
+    /// they can't be known before the API call
+    pub fn needs_results(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Score | Part::Motion))
+    }
+
+    /// File name for one frame
+    pub fn render(&self, ctx: &FrameContext) -> String {
+        let mut name = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => name.push_str(text),
+                Part::Index { width } => {
+                    let _ = write!(name, "{:0width$}", ctx.index, width = *width);
+                }
+                Part::Character => name.push_str(&sanitize(ctx.character.unwrap_or("unknown"))),
+                Part::Motion => name.push_str(&sanitize(ctx.motion.unwrap_or("unknown"))),
+                Part::Score => {
+                    let _ = write!(name, "{:.2}", ctx.score);
+                }
+            }
+        }
+        name
+    }
+}
+
+impl Default for OutputTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TEMPLATE).expect("default template is valid")
+    }
+}
+
+/// A parsed `--organize-by`, e.g. `{character}/{motion}/{pair}`: the folder under the
+/// batch output root that each pair is written to
+#[derive(Debug, Clone)]
+pub struct PairLayout {
+    parts: Vec<LayoutPart>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum LayoutPart {
+    Literal(String),
+    Character,
+    Motion,
+    Pair,
+}
+
+impl PairLayout {
+    /// Parse a layout, rejecting unknown placeholders, layouts without `{pair}`, and any
+    /// that could leave the output root (absolute paths or `..`)
+    pub fn parse(layout: &str) -> Result<Self> {
+        let escapes = layout.starts_with(['/', '\\'])
+            || layout.split(['/', '\\']).any(|segment| segment == "..")
+            || PathBuf::from(layout).is_absolute();
+        if escapes {
+            bail!("--organize-by must stay inside the output directory: {layout}");
+        }
+
+        let parts = split_placeholders(layout, "--organize-by")?
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Literal(text) => Ok(LayoutPart::Literal(text)),
+                Piece::Placeholder("character") => Ok(LayoutPart::Character),
+                Piece::Placeholder("motion") => Ok(LayoutPart::Motion),
+                Piece::Placeholder("pair") => Ok(LayoutPart::Pair),
+                Piece::Placeholder(other) => {
+                    bail!("Unknown --organize-by placeholder: {{{other}}}")
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Without the pair every pair of a character/motion would share one folder
+        if !parts.contains(&LayoutPart::Pair) {
+            bail!("--organize-by must contain a {{pair}} placeholder: {layout}");
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Whether the folder depends on the auto-detected motion, so it can't be known
+    /// before the pair is generated unless `--motion-type` is given
+    pub fn needs_motion(&self) -> bool {
+        self.parts.contains(&LayoutPart::Motion)
+    }
+
+    /// Folder for one pair, relative to the output root; missing values become `unknown`
+    pub fn render(&self, pair: &str, character: Option<&str>, motion: Option<&str>) -> PathBuf {
+        let mut path = String::new();
+        for part in &self.parts {
+            match part {
+                LayoutPart::Literal(text) => path.push_str(text),
+                LayoutPart::Character => path.push_str(&folder_name(character)),
+                LayoutPart::Motion => path.push_str(&folder_name(motion)),
+                LayoutPart::Pair => path.push_str(pair),
+            }
+        }
+        PathBuf::from(path)
+    }
+}
+
+impl Default for PairLayout {
+    fn default() -> Self {
+        Self::parse(DEFAULT_PAIR_LAYOUT).expect("default layout is valid")
+    }
+}
+
+enum Piece<'a> {
+    Literal(String),
+    Placeholder(&'a str),
+}
+
+/// Split a template into literal text and the names inside `{...}`
+fn split_placeholders<'a>(template: &'a str, what: &str) -> Result<Vec<Piece<'a>>> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        literal.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            bail!("Unclosed '{{' in {what}: {template}");
+        };
+
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+        }
+        pieces.push(Piece::Placeholder(&rest[open + 1..open + close]));
+        rest = &rest[open + close + 1..];
+    }
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        pieces.push(Piece::Literal(literal));
+    }
+
+    Ok(pieces)
+}
+
+/// Keep substituted values from introducing directories into the file name
+fn sanitize(value: &str) -> String {
+    value.replace(['/', '\\'], "_")
+}
+
+/// A substituted value as a single folder name that can't climb out of its parent
+fn folder_name(value: Option<&str>) -> String {
+    match value.map(sanitize) {
+        Some(name) if !matches!(name.as_str(), "" | "." | "..") => name,
+        _ => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let ctx = FrameContext {
+            index: 1,
+            character: Some("hero"),
+            motion: Some("walk"),
+            score: 0.876,
+        };
+
+        let template =
+            OutputTemplate::parse("shot010_{character}_{motion}_{index:03}_{score}.png").unwrap();
+        assert_eq!(template.render(&ctx), "shot010_hero_walk_001_0.88.png");
+
+        let template = OutputTemplate::parse("inbetween_{index}.png").unwrap();
+        assert_eq!(template.render(&ctx), "inbetween_1.png");
+
+        assert_eq!(OutputTemplate::default().render(&ctx), "0001.png");
+        assert!(!OutputTemplate::default().needs_results());
+    }
+
+    #[test]
+    fn test_template_requires_index() {
+        assert!(OutputTemplate::parse("{character}.png").is_err());
+        assert!(OutputTemplate::parse("{index}_{bogus}.png").is_err());
+        assert!(OutputTemplate::parse("{index.png").is_err());
+    }
+
+    #[test]
+    fn test_pair_layout() {
+        let layout = PairLayout::parse("{character}/{motion}/{pair}").unwrap();
+        assert!(layout.needs_motion());
+        assert_eq!(
+            layout.render("pair_0003", Some("hero"), Some("walk")),
+            PathBuf::from("hero/walk/pair_0003")
+        );
+        assert_eq!(
+            layout.render("pair_0003", None, Some("..")),
+            PathBuf::from("unknown/unknown/pair_0003")
+        );
+        assert_eq!(
+            layout.render("pair_0000", Some("a/b"), Some("run")),
+            PathBuf::from("a_b/run/pair_0000")
+        );
+
+        let flat = PairLayout::default();
+        assert!(!flat.needs_motion());
+        assert_eq!(
+            flat.render("pair_0001", Some("hero"), None),
+            PathBuf::from("pair_0001")
+        );
+
+        assert!(PairLayout::parse("{character}").is_err());
+        assert!(PairLayout::parse("{shot}/{pair}").is_err());
+        assert!(PairLayout::parse("../{pair}").is_err());
+        assert!(PairLayout::parse("{character}/../../{pair}").is_err());
+        assert!(PairLayout::parse("/tmp/{pair}").is_err());
+    }
+}
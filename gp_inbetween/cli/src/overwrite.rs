@@ -0,0 +1,85 @@
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+/// What to do when the output directory already holds files we would write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    /// Refuse if a previous run's metadata.json is present
+    ProtectMetadata,
+    /// Refuse if any file we would write already exists
+    NoClobber,
+    /// Replace existing files
+    Overwrite,
+}
+
+impl OverwritePolicy {
+    pub fn from_flags(overwrite: bool, no_clobber: bool) -> Self {
+        if overwrite {
+            Self::Overwrite
+        } else if no_clobber {
+            Self::NoClobber
+        } else {
+            Self::ProtectMetadata
+        }
+    }
+
+    /// Fail if writing `frame_paths` and metadata.json into `output_dir` would
+    /// replace files this policy protects
+    pub fn check(self, output_dir: &Path, frame_paths: &[PathBuf]) -> Result<()> {
+        let metadata_path = output_dir.join("metadata.json");
+
+        match self {
+            Self::Overwrite => {}
+            Self::ProtectMetadata => {
+                if metadata_path.exists() {
+                    bail!(
+                        "{} already holds a previous generation (metadata.json exists); \
+                         pass --overwrite to replace it",
+                        output_dir.display()
+                    );
+                }
+            }
+            Self::NoClobber => {
+                let existing = frame_paths
+                    .iter()
+                    .chain(std::iter::once(&metadata_path))
+                    .find(|path| path.exists());
+                if let Some(path) = existing {
+                    bail!("Refusing to overwrite {} (--no-clobber)", path.display());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_refuses_existing_targets() {
+        let dir = tempdir().unwrap();
+        let frame = dir.path().join("0000.png");
+        std::fs::write(&frame, b"hand-edited").unwrap();
+        let targets = vec![frame, dir.path().join("0001.png")];
+
+        // A stray frame only matters with --no-clobber
+        assert!(OverwritePolicy::NoClobber
+            .check(dir.path(), &targets)
+            .is_err());
+        assert!(OverwritePolicy::ProtectMetadata
+            .check(dir.path(), &targets)
+            .is_ok());
+
+        std::fs::write(dir.path().join("metadata.json"), b"{}").unwrap();
+        assert!(OverwritePolicy::ProtectMetadata
+            .check(dir.path(), &targets)
+            .is_err());
+        assert!(OverwritePolicy::Overwrite
+            .check(dir.path(), &targets)
+            .is_ok());
+    }
+}
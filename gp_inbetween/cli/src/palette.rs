@@ -0,0 +1,245 @@
+//! Palette quantization for `--optimize`.
+//!
+//! Colors are compared premultiplied, so a faint antialiased edge pixel can share an entry
+//! with its neighbours while opaque line color stays exact. Any palette that would move a
+//! visible pixel further than the requested quality allows is thrown away, and the frame
+//! is written as lossless RGBA instead.
+
+use crate::frame_format::{add_text, encode_png};
+use anyhow::Result;
+use image::{DynamicImage, RgbaImage};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const MAX_COLORS: usize = 256;
+const TRANSPARENT: [u8; 4] = [0; 4];
+
+/// Write an 8-bit frame as small as `quality` (1-100) allows: an indexed PNG when a
+/// palette fits, else RGBA at maximum compression.
+///
+/// Quality 100 only accepts exact palettes; each step below lets a visible channel drift by
+/// up to half a level.
+pub fn save_optimized(
+    frame: &DynamicImage,
+    path: &Path,
+    quality: u8,
+    text: &[(String, String)],
+) -> Result<()> {
+    let mut rgba = frame.to_rgba8();
+    // Color under fully transparent pixels is invisible; clearing it lets the runs compress
+    for pixel in rgba.pixels_mut() {
+        if pixel[3] == 0 {
+            pixel.0 = TRANSPARENT;
+        }
+    }
+
+    let max_error = f32::from(100 - quality.min(100)) / 2.0;
+    let writer = BufWriter::new(File::create(path)?);
+    if let Some(palette) = quantize(&rgba, max_error) {
+        return write_indexed(writer, &rgba, &palette, text);
+    }
+
+    let lossless = if frame.color().has_alpha() {
+        DynamicImage::ImageRgba8(rgba)
+    } else {
+        DynamicImage::ImageRgb8(frame.to_rgb8())
+    };
+    encode_png(writer, &lossless, png::Compression::Best, text)
+}
+
+struct Palette {
+    colors: Vec<[u8; 4]>,
+    index: HashMap<[u8; 4], u8>,
+}
+
+/// Build a palette for the image, or `None` if no 256-color palette stays within
+/// `max_error` of every pixel
+fn quantize(image: &RgbaImage, max_error: f32) -> Option<Palette> {
+    let mut histogram: HashMap<[u8; 4], u32> = HashMap::new();
+    for pixel in image.pixels() {
+        *histogram.entry(pixel.0).or_default() += 1;
+    }
+    // Sorted so the same frame always produces the same file
+    let mut colors: Vec<([u8; 4], u32)> = histogram.into_iter().collect();
+    colors.sort_unstable();
+
+    let mut palette = if colors.len() <= MAX_COLORS {
+        colors.iter().map(|&(color, _)| color).collect()
+    } else if max_error == 0.0 {
+        return None;
+    } else {
+        median_cut(&colors)
+    };
+    // Translucent entries first keeps the tRNS chunk short
+    palette.sort_unstable_by_key(|color| (color[3], *color));
+
+    let mut index = HashMap::with_capacity(colors.len());
+    for &(color, _) in &colors {
+        let (entry, error) = palette
+            .iter()
+            .enumerate()
+            .map(|(entry, &candidate)| (entry, visible_error(color, candidate)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+        if error > max_error {
+            return None;
+        }
+        index.insert(color, entry as u8);
+    }
+
+    Some(Palette {
+        colors: palette,
+        index,
+    })
+}
+
+/// Split the color histogram into at most 256 boxes along their widest channel and
+/// average each box. Fully transparent pixels keep an entry of their own so the
+/// background never picks up a tint.
+fn median_cut(colors: &[([u8; 4], u32)]) -> Vec<[u8; 4]> {
+    let has_transparent = colors.iter().any(|&(color, _)| color == TRANSPARENT);
+    let budget = MAX_COLORS - usize::from(has_transparent);
+    let mut boxes: Vec<Vec<([u8; 4], u32)>> = vec![colors
+        .iter()
+        .copied()
+        .filter(|&(color, _)| color != TRANSPARENT)
+        .collect()];
+
+    while boxes.len() < budget {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| (i, widest_channel(colors)))
+            .max_by(|a, b| a.1 .1.total_cmp(&b.1 .1));
+        let Some((i, (channel, _))) = widest else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_unstable_by(|a, b| {
+            premultiply(a.0)[channel].total_cmp(&premultiply(b.0)[channel])
+        });
+        let total: u64 = colors.iter().map(|&(_, count)| u64::from(count)).sum();
+        let mut seen = 0;
+        let median = colors
+            .iter()
+            .position(|&(_, count)| {
+                seen += u64::from(count);
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+        let upper = colors.split_off((median + 1).clamp(1, colors.len() - 1));
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut palette: Vec<[u8; 4]> = boxes.iter().map(|colors| average(colors)).collect();
+    if has_transparent {
+        palette.push(TRANSPARENT);
+    }
+    palette
+}
+
+/// The channel with the largest premultiplied spread in a box, and that spread
+fn widest_channel(colors: &[([u8; 4], u32)]) -> (usize, f32) {
+    let mut low = [f32::MAX; 4];
+    let mut high = [f32::MIN; 4];
+    for &(color, _) in colors {
+        for (channel, value) in premultiply(color).into_iter().enumerate() {
+            low[channel] = low[channel].min(value);
+            high[channel] = high[channel].max(value);
+        }
+    }
+    (0..4)
+        .map(|channel| (channel, high[channel] - low[channel]))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
+
+/// Pixel-count weighted mean of a box, averaged premultiplied
+fn average(colors: &[([u8; 4], u32)]) -> [u8; 4] {
+    let mut sum = [0.0f64; 4];
+    let mut total = 0.0f64;
+    for &(color, count) in colors {
+        let weight = f64::from(count);
+        for (channel, value) in premultiply(color).into_iter().enumerate() {
+            sum[channel] += f64::from(value) * weight;
+        }
+        total += weight;
+    }
+
+    let alpha = sum[3] / total;
+    let straight = |premultiplied: f64| {
+        if alpha > 0.0 {
+            (premultiplied / total * 255.0 / alpha)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        } else {
+            0
+        }
+    };
+    [
+        straight(sum[0]),
+        straight(sum[1]),
+        straight(sum[2]),
+        alpha.round() as u8,
+    ]
+}
+
+fn premultiply(color: [u8; 4]) -> [f32; 4] {
+    let alpha = f32::from(color[3]);
+    [
+        f32::from(color[0]) * alpha / 255.0,
+        f32::from(color[1]) * alpha / 255.0,
+        f32::from(color[2]) * alpha / 255.0,
+        alpha,
+    ]
+}
+
+/// Largest change in any channel once composited, in 8-bit levels
+fn visible_error(a: [u8; 4], b: [u8; 4]) -> f32 {
+    let (a, b) = (premultiply(a), premultiply(b));
+    (0..4)
+        .map(|channel| (a[channel] - b[channel]).abs())
+        .fold(0.0, f32::max)
+}
+
+fn write_indexed(
+    writer: impl Write,
+    image: &RgbaImage,
+    palette: &Palette,
+    text: &[(String, String)],
+) -> Result<()> {
+    let mut encoder = png::Encoder::new(writer, image.width(), image.height());
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Best);
+    // Filters predict smooth gradients; palette indices aren't one
+    encoder.set_filter(png::FilterType::NoFilter);
+    encoder.set_palette(
+        palette
+            .colors
+            .iter()
+            .flat_map(|color| [color[0], color[1], color[2]])
+            .collect::<Vec<u8>>(),
+    );
+    let translucent: Vec<u8> = palette
+        .colors
+        .iter()
+        .map(|color| color[3])
+        .take_while(|&alpha| alpha < 255)
+        .collect();
+    if !translucent.is_empty() {
+        encoder.set_trns(translucent);
+    }
+    add_text(&mut encoder, text)?;
+
+    let indices: Vec<u8> = image
+        .pixels()
+        .map(|pixel| palette.index[&pixel.0])
+        .collect();
+    encoder.write_header()?.write_image_data(&indices)?;
+    Ok(())
+}
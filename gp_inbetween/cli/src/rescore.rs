@@ -0,0 +1,228 @@
+//! Re-scoring archived generations for `rescore`.
+//!
+//! A pair folder in the archive is laid out like an unpacked review bundle: its
+//! `metadata.json`, the keyframes as `keyframes/frame_a.png` and `keyframes/frame_b.png`,
+//! and the numbered frames in `frames/` or beside `metadata.json`. Folders are found at
+//! any depth, so `<character>/<pair>` and flat archives both work.
+
+use crate::list_numbered_frames;
+use anyhow::{Context, Result};
+use gp_core::{Easing, Generator, OutputMetadata};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// One archived generation with everything needed to score it again
+pub struct ArchivedPair {
+    pub dir: PathBuf,
+    pub frame_a: PathBuf,
+    pub frame_b: PathBuf,
+    pub frames: Vec<PathBuf>,
+    pub metadata: OutputMetadata,
+}
+
+/// Old and new scores of one archived generation
+#[derive(Debug, Serialize)]
+pub struct PairReport {
+    pub path: PathBuf,
+    pub frames: Vec<FrameReport>,
+    /// Mean of the archived scores (None if they don't line up with the frames)
+    pub previous_mean: Option<f32>,
+    pub mean: f32,
+    pub previous_auto_accepted: Option<usize>,
+    pub auto_accepted: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FrameReport {
+    pub path: PathBuf,
+    pub previous_score: Option<f32>,
+    pub score: f32,
+    pub auto_accept: bool,
+}
+
+/// Every pair folder under `root`, in path order. Folders with a metadata.json but no
+/// keyframes or frames are skipped with a warning.
+pub fn find_pairs(root: &Path) -> Result<Vec<ArchivedPair>> {
+    let mut pairs = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        if dir.join("metadata.json").is_file() {
+            match load_pair(&dir) {
+                Ok(pair) => pairs.push(pair),
+                Err(e) => log::warn!("Skipping {}: {:#}", dir.display(), e),
+            }
+            continue;
+        }
+
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            }
+        }
+    }
+
+    pairs.sort_by(|a, b| a.dir.cmp(&b.dir));
+    Ok(pairs)
+}
+
+fn load_pair(dir: &Path) -> Result<ArchivedPair> {
+    let metadata_path = dir.join("metadata.json");
+    let contents = std::fs::read_to_string(&metadata_path)
+        .with_context(|| format!("Failed to read {}", metadata_path.display()))?;
+    let metadata: OutputMetadata = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse {}", metadata_path.display()))?;
+
+    let keyframes = dir.join("keyframes");
+    let (frame_a, frame_b) = (keyframes.join("frame_a.png"), keyframes.join("frame_b.png"));
+    if !frame_a.is_file() || !frame_b.is_file() {
+        anyhow::bail!("no keyframes/frame_a.png and keyframes/frame_b.png");
+    }
+
+    let frames_dir = dir.join("frames");
+    let frames = if frames_dir.is_dir() {
+        list_numbered_frames(&frames_dir)?
+    } else {
+        list_numbered_frames(dir)?
+    };
+    if frames.is_empty() {
+        anyhow::bail!("no numbered frame PNGs");
+    }
+
+    Ok(ArchivedPair {
+        dir: dir.to_path_buf(),
+        frame_a,
+        frame_b,
+        frames,
+        metadata,
+    })
+}
+
+/// Score a pair's frames with the current scorer, at the positions and with the
+/// character and motion type its generation recorded
+pub fn rescore_pair(generator: &mut Generator, pair: &ArchivedPair) -> Result<PairReport> {
+    let metadata = &pair.metadata;
+    let easing = if metadata.positions.len() == pair.frames.len() {
+        Easing::Custom(metadata.positions.clone())
+    } else {
+        Easing::Linear
+    };
+    generator.set_easing(easing);
+    let results = generator.rescore_frames(
+        &pair.frames,
+        &pair.frame_a,
+        &pair.frame_b,
+        metadata.character.as_deref(),
+        metadata.motion_type.as_deref(),
+    )?;
+
+    // Scores are matched by index, which only holds if no frame went missing
+    let previous = if metadata.confidence_scores.len() == pair.frames.len() {
+        Some(&metadata.confidence_scores)
+    } else {
+        log::warn!(
+            "{} records {} score(s) for {} frame(s); not comparing",
+            pair.dir.display(),
+            metadata.confidence_scores.len(),
+            pair.frames.len()
+        );
+        None
+    };
+
+    let frames: Vec<FrameReport> = pair
+        .frames
+        .iter()
+        .zip(&results.frames)
+        .enumerate()
+        .map(|(i, (path, frame))| FrameReport {
+            path: path.clone(),
+            previous_score: previous.map(|scores| scores[i]),
+            score: frame.score,
+            auto_accept: frame.auto_accept,
+        })
+        .collect();
+
+    Ok(PairReport {
+        path: pair.dir.clone(),
+        previous_mean: previous.map(|scores| scores.iter().sum::<f32>() / scores.len() as f32),
+        mean: results.sequence_score(),
+        previous_auto_accepted: previous.map(|_| {
+            metadata
+                .auto_accept
+                .iter()
+                .filter(|accepted| **accepted)
+                .count()
+        }),
+        auto_accepted: frames.iter().filter(|frame| frame.auto_accept).count(),
+        frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gp_core::Config;
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use tempfile::tempdir;
+
+    fn block(left: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, y| {
+            let inside = (left..left + 8).contains(&x) && (8..24).contains(&y);
+            Rgba([20, 20, 20, if inside { 255 } else { 0 }])
+        }))
+    }
+
+    #[test]
+    fn test_rescore_tiny_archive() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("archive");
+
+        let pair = archive.join("hero").join("walk_001");
+        std::fs::create_dir_all(pair.join("keyframes")).unwrap();
+        std::fs::create_dir_all(pair.join("frames")).unwrap();
+        block(4).save(pair.join("keyframes/frame_a.png")).unwrap();
+        block(16).save(pair.join("keyframes/frame_b.png")).unwrap();
+        block(8).save(pair.join("frames/0000.png")).unwrap();
+        block(12).save(pair.join("frames/0001.png")).unwrap();
+        let metadata = serde_json::json!({
+            "character": "hero",
+            "motion_type": "walk",
+            "confidence_scores": [0.4, 0.5],
+            "auto_accept": [false, false],
+            "auto_accept_threshold": 0.85,
+            "positions": [0.33, 0.67],
+        });
+        std::fs::write(pair.join("metadata.json"), metadata.to_string()).unwrap();
+
+        // Archived without its keyframes, so it can't be scored
+        let incomplete = archive.join("hero").join("walk_002");
+        std::fs::create_dir_all(&incomplete).unwrap();
+        std::fs::write(incomplete.join("metadata.json"), metadata.to_string()).unwrap();
+        block(8).save(incomplete.join("0000.png")).unwrap();
+
+        let pairs = find_pairs(&archive).unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].dir, pair);
+        assert_eq!(pairs[0].frames.len(), 2);
+
+        let mut config = Config::default();
+        config.api.backend = "crossfade".to_string();
+        config.preprocessing.target_resolution = 32;
+        config.feedback_log_path = Some(dir.path().join("feedback.jsonl").display().to_string());
+        let mut generator = Generator::new(config).unwrap();
+        let report = rescore_pair(&mut generator, &pairs[0]).unwrap();
+
+        assert_eq!(report.frames.len(), 2);
+        assert_eq!(report.frames[1].previous_score, Some(0.5));
+        assert!((report.previous_mean.unwrap() - 0.45).abs() < 1e-6);
+        assert_eq!(report.previous_auto_accepted, Some(0));
+        for frame in &report.frames {
+            assert!((0.0..=1.0).contains(&frame.score));
+        }
+        // Scoring doesn't touch the archive
+        let archived = std::fs::read_to_string(pair.join("metadata.json")).unwrap();
+        assert_eq!(archived, metadata.to_string());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::OutputMetadata;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// File in the batch output root listing the pairs that finished
+pub const PROGRESS_FILE: &str = ".progress";
+
+/// Tracks which pairs of a batch have completed, so an interrupted run can resume
+pub struct BatchProgress {
+    path: PathBuf,
+    completed: HashSet<String>,
+}
+
+impl BatchProgress {
+    /// Load the progress file from a batch output root (empty if none exists yet)
+    pub fn load(output_root: &Path) -> Result<Self> {
+        let path = output_root.join(PROGRESS_FILE);
+
+        let completed = if path.exists() {
+            std::fs::read_to_string(&path)
+                .context("Failed to read batch progress file")?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        Ok(Self { path, completed })
+    }
+
+    /// Start a fresh progress file, forgetting any previous run
+    pub fn reset(output_root: &Path) -> Result<Self> {
+        let path = output_root.join(PROGRESS_FILE);
+        if path.exists() {
+            std::fs::remove_file(&path).context("Failed to reset batch progress file")?;
+        }
+
+        Ok(Self {
+            path,
+            completed: HashSet::new(),
+        })
+    }
+
+    /// Record a pair as finished, persisting it immediately
+    pub fn mark_complete(&mut self, pair: &str) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("Failed to open batch progress file")?;
+
+        writeln!(file, "{pair}")?;
+        self.completed.insert(pair.to_string());
+
+        Ok(())
+    }
+
+    /// Whether the progress file lists this pair as finished
+    pub fn is_recorded(&self, pair: &str) -> bool {
+        self.completed.contains(pair)
+    }
+
+    /// Number of pairs recorded as finished
+    pub fn completed_count(&self) -> usize {
+        self.completed.len()
+    }
+}
+
+/// Check whether a pair's output folder holds a finished generation.
+///
+/// metadata.json is written after every frame, so a folder without a parseable one, or
+/// with one marked incomplete, was interrupted mid-pair and must be regenerated.
+pub fn is_pair_complete(pair_dir: &Path) -> bool {
+    let Ok(contents) = std::fs::read_to_string(pair_dir.join("metadata.json")) else {
+        return false;
+    };
+
+    serde_json::from_str::<OutputMetadata>(&contents).is_ok_and(|metadata| !metadata.incomplete)
+}
+
+/// Run `task` on every item using up to `jobs` worker threads, returning each item's
+/// result in input order. A failing item doesn't stop the others.
+pub fn run_concurrently<T, F>(items: &[T], jobs: usize, task: F) -> Vec<Result<()>>
+where
+    T: Sync,
+    F: Fn(&T) -> Result<()> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let workers = jobs.clamp(1, items.len().max(1));
+
+    let mut results: Vec<(usize, Result<()>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break done;
+                        };
+                        done.push((index, task(item)));
+                    }
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_metadata(dir: &Path) {
+        let metadata = OutputMetadata {
+            character: None,
+            motion_type: Some("walk".to_string()),
+            confidence_scores: vec![0.9],
+            auto_accept: vec![true],
+            auto_accept_threshold: 0.85,
+            seed: None,
+            near_duplicate: vec![false],
+            style_strength: None,
+            model_version: None,
+            positions: vec![0.5],
+            repeats_previous: vec![false],
+            auto_reject: vec![false],
+            aspect_distorted: vec![false],
+            synthesized: vec![false],
+            scoring_errors: vec![None],
+            smoothed_scores: vec![None],
+            incomplete: false,
+        };
+        std::fs::write(
+            dir.join("metadata.json"),
+            serde_json::to_string(&metadata).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_resume_skips_only_finished_pairs() {
+        let root = tempdir().unwrap();
+
+        // Finished pair
+        let done = root.path().join("pair_0000");
+        std::fs::create_dir_all(&done).unwrap();
+        std::fs::write(done.join("0000.png"), b"png").unwrap();
+        write_metadata(&done);
+
+        // Crashed mid-pair: frames written, metadata missing
+        let partial = root.path().join("pair_0001");
+        std::fs::create_dir_all(&partial).unwrap();
+        std::fs::write(partial.join("0000.png"), b"png").unwrap();
+
+        // Crashed while writing metadata
+        let truncated = root.path().join("pair_0002");
+        std::fs::create_dir_all(&truncated).unwrap();
+        std::fs::write(truncated.join("metadata.json"), b"{\"character\": nu").unwrap();
+
+        assert!(is_pair_complete(&done));
+        assert!(!is_pair_complete(&partial));
+        assert!(!is_pair_complete(&truncated));
+        assert!(!is_pair_complete(&root.path().join("pair_0003")));
+    }
+
+    #[test]
+    fn test_progress_roundtrip() {
+        let root = tempdir().unwrap();
+
+        let mut progress = BatchProgress::load(root.path()).unwrap();
+        assert_eq!(progress.completed_count(), 0);
+        progress.mark_complete("pair_0000").unwrap();
+        progress.mark_complete("pair_0001").unwrap();
+
+        let reloaded = BatchProgress::load(root.path()).unwrap();
+        assert!(reloaded.is_recorded("pair_0000"));
+        assert!(reloaded.is_recorded("pair_0001"));
+        assert!(!reloaded.is_recorded("pair_0002"));
+
+        let fresh = BatchProgress::reset(root.path()).unwrap();
+        assert_eq!(fresh.completed_count(), 0);
+        assert!(!root.path().join(PROGRESS_FILE).exists());
+    }
+
+    #[test]
+    fn test_run_concurrently_isolates_failures() {
+        let pairs: Vec<usize> = (0..12).collect();
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        // Stand-in for a generator call: slow enough to overlap, and pair 5 fails
+        let results = run_concurrently(&pairs, 3, |pair| {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            if *pair == 5 {
+                anyhow::bail!("prediction failed");
+            }
+            Ok(())
+        });
+
+        assert_eq!(results.len(), 12);
+        let failed: Vec<_> = (0..12).filter(|i| results[*i].is_err()).collect();
+        assert_eq!(failed, vec![5]);
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert!(peak.load(Ordering::SeqCst) > 1);
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Per-entry index file listing the cached frames
+const INDEX_FILE: &str = "index.json";
+
+/// On-disk cache of API results, keyed by a fingerprint of everything sent to the model
+pub struct GenerationCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheIndex {
+    frames: Vec<String>,
+    created: u64,
+}
+
+impl GenerationCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).context("Failed to create generation cache directory")?;
+        Ok(Self { dir })
+    }
+
+    /// Frames stored under `key`, or `None` on a miss.
+    ///
+    /// Entries are only readable once their index is written, so an interrupted store
+    /// counts as a miss rather than returning a partial sequence.
+    pub fn load(&self, key: &str) -> Result<Option<Vec<DynamicImage>>> {
+        let entry = self.entry_dir(key);
+        let Ok(contents) = std::fs::read_to_string(entry.join(INDEX_FILE)) else {
+            return Ok(None);
+        };
+        let Ok(index) = serde_json::from_str::<CacheIndex>(&contents) else {
+            return Ok(None);
+        };
+
+        let mut frames = Vec::with_capacity(index.frames.len());
+        for name in &index.frames {
+            match image::open(entry.join(name)) {
+                Ok(frame) => frames.push(frame),
+                Err(e) => {
+                    log::warn!("Ignoring damaged cache entry {}: {}", key, e);
+                    return Ok(None);
+                }
+            }
+        }
+
+        Ok(Some(frames))
+    }
+
+    /// Store frames under `key`, replacing any previous entry
+    pub fn store(&self, key: &str, frames: &[DynamicImage]) -> Result<()> {
+        let entry = self.entry_dir(key);
+        std::fs::create_dir_all(&entry).context("Failed to create cache entry")?;
+
+        // The old index would vouch for frames overwritten below, so an interrupted
+        // store could load as a mix of old and new frames
+        match std::fs::remove_file(entry.join(INDEX_FILE)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context("Failed to replace cache entry"),
+        }
+
+        let mut names = Vec::with_capacity(frames.len());
+        for (i, frame) in frames.iter().enumerate() {
+            let name = format!("{:04}.png", i);
+            frame.save(entry.join(&name))?;
+            names.push(name);
+        }
+
+        let index = CacheIndex {
+            frames: names,
+            created: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        };
+        std::fs::write(entry.join(INDEX_FILE), serde_json::to_string(&index)?)?;
+
+        Ok(())
+    }
+
+    fn entry_dir(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = tempdir().unwrap();
+        let cache = GenerationCache::new(dir.path()).unwrap();
+
+        assert!(cache.load("abc").unwrap().is_none());
+
+        let frames = vec![DynamicImage::new_rgba8(4, 4), DynamicImage::new_rgba8(4, 4)];
+        cache.store("abc", &frames).unwrap();
+
+        let loaded = cache.load("abc").unwrap().unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].width(), 4);
+
+        // An entry without its index was interrupted mid-store
+        std::fs::remove_file(dir.path().join("abc").join(INDEX_FILE)).unwrap();
+        assert!(cache.load("abc").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_failed_replacement_is_a_miss() {
+        let dir = tempdir().unwrap();
+        let cache = GenerationCache::new(dir.path()).unwrap();
+        let frames = vec![DynamicImage::new_rgba8(4, 4), DynamicImage::new_rgba8(4, 4)];
+        cache.store("abc", &frames).unwrap();
+
+        // Replacing it overwrites both old frames, then fails on the third
+        std::fs::create_dir(dir.path().join("abc").join("0002.png")).unwrap();
+        let replacement = vec![DynamicImage::new_rgba8(8, 8); 3];
+        assert!(cache.store("abc", &replacement).is_err());
+
+        assert!(cache.load("abc").unwrap().is_none());
+    }
+}
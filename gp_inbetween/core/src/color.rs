@@ -0,0 +1,136 @@
+/// CIELAB coordinates (D65 white point): lightness 0-100, then the a* (green-red) and
+/// b* (blue-yellow) axes
+pub type Lab = [f32; 3];
+
+/// D65 reference white in XYZ
+const WHITE: [f64; 3] = [0.950_47, 1.0, 1.088_83];
+
+/// Convert an sRGB color (channels 0.0 - 1.0) to CIELAB
+pub fn srgb_to_lab(rgb: [f32; 3]) -> Lab {
+    let [r, g, b] = rgb.map(|c| {
+        let c = f64::from(c);
+        if c <= 0.040_45 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    let xyz = [
+        0.4124 * r + 0.3576 * g + 0.1805 * b,
+        0.2126 * r + 0.7152 * g + 0.0722 * b,
+        0.0193 * r + 0.1192 * g + 0.9505 * b,
+    ];
+
+    let f = |t: f64| {
+        const DELTA: f64 = 6.0 / 29.0;
+        if t > DELTA.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+    let [fx, fy, fz] = [0, 1, 2].map(|i| f(xyz[i] / WHITE[i]));
+    [
+        (116.0 * fy - 16.0) as f32,
+        (500.0 * (fx - fy)) as f32,
+        (200.0 * (fy - fz)) as f32,
+    ]
+}
+
+/// Perceptual difference between two colors (CIEDE2000 ΔE). About 1 is the smallest
+/// difference people notice side by side; above 10 reads as a different color.
+pub fn delta_e_2000(lab1: Lab, lab2: Lab) -> f32 {
+    let [l1, a1, b1] = lab1.map(f64::from);
+    let [l2, a2, b2] = lab2.map(f64::from);
+    let pow25_7 = 25.0f64.powi(7);
+
+    // Stretch a* for low-chroma colors, where CIELAB underestimates differences
+    let mean_chroma = (a1.hypot(b1) + a2.hypot(b2)) / 2.0;
+    let boost = 0.5 * (1.0 - (mean_chroma.powi(7) / (mean_chroma.powi(7) + pow25_7)).sqrt());
+    let (a1, a2) = ((1.0 + boost) * a1, (1.0 + boost) * a2);
+    let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+    let hue = |a: f64, b: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let (h1, h2) = (hue(a1, b1), hue(a2, b2));
+    let achromatic = c1 * c2 == 0.0;
+
+    let delta_l = l2 - l1;
+    let delta_c = c2 - c1;
+    let delta_h = if achromatic {
+        0.0
+    } else {
+        let dh = h2 - h1;
+        if dh > 180.0 {
+            dh - 360.0
+        } else if dh < -180.0 {
+            dh + 360.0
+        } else {
+            dh
+        }
+    };
+    let delta_h = 2.0 * (c1 * c2).sqrt() * (delta_h / 2.0).to_radians().sin();
+
+    let mean_l = (l1 + l2) / 2.0;
+    let mean_c = (c1 + c2) / 2.0;
+    let mean_h = if achromatic {
+        h1 + h2
+    } else if (h1 - h2).abs() <= 180.0 {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) / 2.0
+    } else {
+        (h1 + h2 - 360.0) / 2.0
+    };
+
+    let cos = |degrees: f64| degrees.to_radians().cos();
+    let hue_weight =
+        1.0 - 0.17 * cos(mean_h - 30.0) + 0.24 * cos(2.0 * mean_h) + 0.32 * cos(3.0 * mean_h + 6.0)
+            - 0.20 * cos(4.0 * mean_h - 63.0);
+    let s_l = 1.0 + 0.015 * (mean_l - 50.0).powi(2) / (20.0 + (mean_l - 50.0).powi(2)).sqrt();
+    let s_c = 1.0 + 0.045 * mean_c;
+    let s_h = 1.0 + 0.015 * mean_c * hue_weight;
+    // Blue hues need the chroma and hue terms rotated against each other
+    let rotation = 30.0 * (-((mean_h - 275.0) / 25.0).powi(2)).exp();
+    let r_c = 2.0 * (mean_c.powi(7) / (mean_c.powi(7) + pow25_7)).sqrt();
+    let r_t = -(2.0 * rotation).to_radians().sin() * r_c;
+
+    let (lightness, chroma, hue_diff) = (delta_l / s_l, delta_c / s_c, delta_h / s_h);
+    (lightness.powi(2) + chroma.powi(2) + hue_diff.powi(2) + r_t * chroma * hue_diff).sqrt() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_e_2000_reference_pairs() {
+        // From Sharma, Wu and Dalal's CIEDE2000 test data
+        let pairs = [
+            ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+            ([50.0, -1.3802, -84.2814], [50.0, 0.0, -82.7485], 1.0),
+            ([50.0, 2.5, 0.0], [50.0, 0.0, -2.5], 4.3065),
+            (
+                [60.2574, -34.0099, 36.2677],
+                [60.4626, -34.1751, 39.4387],
+                1.2644,
+            ),
+        ];
+        for (lab1, lab2, expected) in pairs {
+            let delta = delta_e_2000(lab1, lab2);
+            assert!(
+                (delta - expected).abs() < 1e-3,
+                "{lab1:?} {lab2:?}: {delta}"
+            );
+            assert!((delta_e_2000(lab2, lab1) - delta).abs() < 1e-4);
+        }
+
+        let white = srgb_to_lab([1.0; 3]);
+        assert!((white[0] - 100.0).abs() < 0.01 && white[1].abs() < 0.01);
+        assert!(delta_e_2000(white, white).abs() < f32::EPSILON);
+    }
+}
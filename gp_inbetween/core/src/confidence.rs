@@ -1,25 +1,148 @@
+use crate::color;
+use crate::config::{ColorSpace, ConfidenceConfig, MotionThresholds};
 use crate::feedback::FeedbackLogger;
-use anyhow::Result;
-use image::{DynamicImage, GenericImageView};
+use crate::stats_cache::ImageStatsCache;
+use anyhow::{Context, Result};
+use image::{ColorType, DynamicImage, GenericImageView, RgbaImage};
+use serde::Serialize;
+
+/// Alpha at or above which a pixel counts as drawn, unless configured otherwise
+pub const DEFAULT_ALPHA_THRESHOLD: u8 = 128;
+
+/// Everything a heuristic may consult when scoring a generated frame
+pub struct ScoringContext<'a> {
+    pub generated: &'a DynamicImage,
+    pub source_a: &'a DynamicImage,
+    pub source_b: &'a DynamicImage,
+    pub motion_type: &'a str,
+    pub character: Option<&'a str>,
+    /// Expected pose halfway between the keyframes, if one was given; consistency checks
+    /// treat it as a third keyframe at 0.5
+    pub reference_mid: Option<&'a DynamicImage>,
+    /// Where the frame sits between the keyframes: 0.0 at A, 1.0 at B
+    pub position: f32,
+    /// Feedback history, if the scorer has access to one
+    pub feedback: Option<&'a FeedbackLogger>,
+    /// Sample sizes for heuristics that read a subset of pixels
+    pub config: &'a ConfidenceConfig,
+    /// Alpha at or above which a pixel counts as drawn
+    pub alpha_threshold: u8,
+    /// Memoized per-image stats, shared across frames and pairs
+    pub stats_cache: &'a ImageStatsCache,
+}
+
+impl<'a> ScoringContext<'a> {
+    /// The references either side of the frame and its position between them: the
+    /// keyframes, or with a midpoint reference, the half of the motion the frame is in
+    pub fn bracketing_references(&self) -> (&'a DynamicImage, &'a DynamicImage, f32) {
+        match self.reference_mid {
+            Some(mid) if self.position <= 0.5 => (self.source_a, mid, self.position * 2.0),
+            Some(mid) => (mid, self.source_b, self.position * 2.0 - 1.0),
+            None => (self.source_a, self.source_b, self.position),
+        }
+    }
+}
+
+/// The drawings generated frames are judged against
+#[derive(Debug, Clone, Copy)]
+pub struct References<'a> {
+    pub source_a: &'a DynamicImage,
+    pub source_b: &'a DynamicImage,
+    /// Expected pose halfway between the keyframes (not itself generated)
+    pub mid: Option<&'a DynamicImage>,
+}
+
+/// A single quality rule that lowers a frame's confidence
+pub trait Heuristic: Send + Sync {
+    /// Stable identifier used as the key in score breakdowns
+    fn name(&self) -> &str;
+
+    /// Amount to subtract from the frame's confidence (0.0 = no concern)
+    fn penalty(&self, ctx: &ScoringContext) -> f32;
+
+    /// Fallible form of [`Heuristic::penalty`], for heuristics that can fail (e.g. ones
+    /// consulting an external service). An error fails scoring for that frame only.
+    fn try_penalty(&self, ctx: &ScoringContext) -> Result<f32> {
+        Ok(self.penalty(ctx))
+    }
+
+    /// Whether the penalty depends on the generated frame. Heuristics that only look at
+    /// the keyframes can be predicted before generating.
+    fn needs_generated_frame(&self) -> bool {
+        true
+    }
+}
+
+/// Final score plus the penalty each heuristic contributed, keyed by heuristic name
+#[derive(Debug, Clone)]
+pub struct ScoreBreakdown {
+    pub score: f32,
+    pub penalties: Vec<(String, f32)>,
+}
 
 pub struct ConfidenceScorer {
     auto_accept_threshold: f32,
+    reject_threshold: Option<f32>,
     feedback_logger: Option<FeedbackLogger>,
+    heuristics: Vec<Box<dyn Heuristic>>,
+    config: ConfidenceConfig,
+    alpha_threshold: u8,
+    stats_cache: ImageStatsCache,
 }
 
 impl ConfidenceScorer {
     pub fn new(auto_accept_threshold: f32) -> Self {
         Self {
             auto_accept_threshold,
+            reject_threshold: None,
             feedback_logger: FeedbackLogger::new().ok(),
+            heuristics: default_heuristics(),
+            stats_cache: ImageStatsCache::new(ConfidenceConfig::default().stats_cache_size),
+            config: ConfidenceConfig::default(),
+            alpha_threshold: DEFAULT_ALPHA_THRESHOLD,
         }
     }
 
+    #[must_use]
+    pub fn with_reject_threshold(mut self, threshold: Option<f32>) -> Self {
+        self.reject_threshold = threshold;
+        self
+    }
+
+    #[must_use]
+    pub fn with_config(mut self, config: ConfidenceConfig) -> Self {
+        self.stats_cache = ImageStatsCache::new(config.stats_cache_size)
+            .with_alpha_threshold(self.alpha_threshold);
+        self.config = config;
+        self
+    }
+
+    /// Alpha at or above which heuristics treat a pixel as drawn. Keep it in step with
+    /// the preprocessing that produced the frames.
+    #[must_use]
+    pub fn with_alpha_threshold(mut self, threshold: u8) -> Self {
+        self.alpha_threshold = threshold;
+        self.stats_cache =
+            ImageStatsCache::new(self.config.stats_cache_size).with_alpha_threshold(threshold);
+        self
+    }
+
+    /// Stats memoized for the images scored so far
+    pub fn stats_cache(&self) -> &ImageStatsCache {
+        &self.stats_cache
+    }
+
+    #[must_use]
     pub fn with_feedback_logger(mut self, logger: FeedbackLogger) -> Self {
         self.feedback_logger = Some(logger);
         self
     }
 
+    /// Register an additional heuristic, applied after the existing ones
+    pub fn add_heuristic(&mut self, heuristic: impl Heuristic + 'static) {
+        self.heuristics.push(Box::new(heuristic));
+    }
+
     /// Score a generated frame based on multiple heuristics
     /// Returns a confidence score between 0.0 and 1.0
     pub fn score_frame(
@@ -30,25 +153,99 @@ impl ConfidenceScorer {
         motion_type: &str,
         character: Option<&str>,
     ) -> Result<f32> {
-        let mut score = 1.0;
+        self.score_frame_breakdown(generated, source_a, source_b, motion_type, character, 0.5)
+            .map(|breakdown| breakdown.score)
+    }
 
-        // Heuristic 1: Basic image validity
-        let validity_penalty = self.check_image_validity(generated);
-        score -= validity_penalty;
+    /// Score a generated frame at `position` (0.0 = A, 1.0 = B), reporting the penalty
+    /// from each heuristic. Use 0.5 when the position is unknown.
+    pub fn score_frame_breakdown(
+        &self,
+        generated: &DynamicImage,
+        source_a: &DynamicImage,
+        source_b: &DynamicImage,
+        motion_type: &str,
+        character: Option<&str>,
+        position: f32,
+    ) -> Result<ScoreBreakdown> {
+        let references = References {
+            source_a,
+            source_b,
+            mid: None,
+        };
+        self.score_against(generated, references, motion_type, character, position)
+    }
 
-        // Heuristic 2: Motion complexity
-        let complexity_penalty = self.assess_motion_complexity(source_a, source_b);
-        score -= complexity_penalty;
+    /// [`Self::score_frame_breakdown`] against keyframes plus an optional midpoint
+    /// reference
+    pub fn score_against(
+        &self,
+        generated: &DynamicImage,
+        references: References,
+        motion_type: &str,
+        character: Option<&str>,
+        position: f32,
+    ) -> Result<ScoreBreakdown> {
+        let ctx = ScoringContext {
+            generated,
+            source_a: references.source_a,
+            source_b: references.source_b,
+            reference_mid: references.mid,
+            motion_type,
+            character,
+            position: position.clamp(0.0, 1.0),
+            feedback: self.feedback_logger.as_ref(),
+            config: &self.config,
+            alpha_threshold: self.alpha_threshold,
+            stats_cache: &self.stats_cache,
+        };
 
-        // Heuristic 3: Historical success rate
-        let historical_penalty = self.check_historical_success(motion_type, character);
-        score -= historical_penalty;
+        let mut score = 1.0;
+        let mut penalties = Vec::with_capacity(self.heuristics.len());
 
-        // Heuristic 4: Color/brightness consistency
-        let consistency_penalty = self.check_color_consistency(generated, source_a, source_b);
-        score -= consistency_penalty;
+        for heuristic in &self.heuristics {
+            let penalty = heuristic
+                .try_penalty(&ctx)
+                .with_context(|| format!("Heuristic {} failed", heuristic.name()))?;
+            score -= penalty;
+            penalties.push((heuristic.name().to_string(), penalty));
+        }
 
-        Ok(score.clamp(0.0, 1.0))
+        Ok(ScoreBreakdown {
+            score: score.clamp(0.0, 1.0),
+            penalties,
+        })
+    }
+
+    /// Penalties predictable from the keyframes alone, skipping heuristics that need a
+    /// generated frame
+    pub fn predict_source_penalties(
+        &self,
+        source_a: &DynamicImage,
+        source_b: &DynamicImage,
+        motion_type: &str,
+        character: Option<&str>,
+    ) -> Vec<(String, f32)> {
+        let ctx = ScoringContext {
+            // Never read by the heuristics evaluated here
+            generated: source_a,
+            source_a,
+            source_b,
+            reference_mid: None,
+            motion_type,
+            character,
+            position: 0.5,
+            feedback: self.feedback_logger.as_ref(),
+            config: &self.config,
+            alpha_threshold: self.alpha_threshold,
+            stats_cache: &self.stats_cache,
+        };
+
+        self.heuristics
+            .iter()
+            .filter(|heuristic| !heuristic.needs_generated_frame())
+            .map(|heuristic| (heuristic.name().to_string(), heuristic.penalty(&ctx)))
+            .collect()
     }
 
     /// Check if a score meets the auto-accept threshold
@@ -56,8 +253,35 @@ impl ConfidenceScorer {
         score >= self.auto_accept_threshold
     }
 
+    /// Check if a score falls below the reject threshold, if one is set
+    pub fn should_auto_reject(&self, score: f32) -> bool {
+        self.reject_threshold.is_some_and(|floor| score < floor)
+    }
+}
+
+/// The built-in heuristics, in the order they are applied
+pub fn default_heuristics() -> Vec<Box<dyn Heuristic>> {
+    vec![
+        Box::new(ImageValidity),
+        Box::new(MotionComplexity),
+        Box::new(HistoricalSuccess),
+        Box::new(ColorConsistency),
+        Box::new(Ghosting),
+    ]
+}
+
+/// Penalizes blank, mostly transparent or single-color frames
+pub struct ImageValidity;
+
+impl ImageValidity {
+    /// Penalty for a frame that is one solid color, which the model returns when it
+    /// fails. Large enough that such frames are never auto-accepted at sane thresholds.
+    const SOLID_COLOR_PENALTY: f32 = 0.5;
+    /// Summed RGBA channel variance (on a 0-1 scale) below which a frame is one color
+    const SOLID_COLOR_VARIANCE: f64 = 1e-4;
+
     /// Check basic image validity (not blank, reasonable dimensions)
-    fn check_image_validity(&self, img: &DynamicImage) -> f32 {
+    fn check_image_validity(&self, img: &DynamicImage, samples: usize, alpha_threshold: u8) -> f32 {
         let (width, height) = img.dimensions();
 
         // Check for blank/empty image
@@ -68,16 +292,22 @@ impl ConfidenceScorer {
         // Sample pixels to check if image has content
         let rgba = img.to_rgba8();
         let total_pixels = (width * height) as usize;
-        let sample_size = total_pixels.min(1000);
+        let sample_size = total_pixels.min(samples.max(1));
         let step = total_pixels / sample_size;
 
+        // Flattened and grayscale frames carry no transparency, so only a lack of any
+        // color variation gives a blank one away
+        if is_opaque(img) {
+            return self.check_solid_color(&rgba, step);
+        }
+
         let mut non_transparent = 0;
         let mut total_alpha = 0u64;
 
         for (i, pixel) in rgba.pixels().enumerate() {
             if i % step == 0 {
                 total_alpha += u64::from(pixel[3]);
-                if pixel[3] > 128 {
+                if pixel[3] >= alpha_threshold {
                     non_transparent += 1;
                 }
             }
@@ -95,12 +325,72 @@ impl ConfidenceScorer {
             return 0.2;
         }
 
-        0.0
+        // Well covered, but possibly by one flat color (e.g. all magenta)
+        self.check_solid_color(&rgba, step)
+    }
+
+    /// Penalize a frame whose sampled pixels are all (nearly) the same color. Line art
+    /// on transparency varies in alpha, and any drawing on a flat background varies in
+    /// color, so only degenerate frames are caught.
+    fn check_solid_color(&self, rgba: &RgbaImage, step: usize) -> f32 {
+        let mut sums = [0.0f64; 4];
+        let mut squares = [0.0f64; 4];
+        let mut count = 0.0f64;
+
+        for pixel in rgba.pixels().step_by(step.max(1)) {
+            for (channel, value) in pixel.0.iter().enumerate() {
+                let value = f64::from(*value) / 255.0;
+                sums[channel] += value;
+                squares[channel] += value * value;
+            }
+            count += 1.0;
+        }
+
+        if count == 0.0 {
+            return 0.0;
+        }
+
+        let variance: f64 = sums
+            .iter()
+            .zip(&squares)
+            .map(|(sum, square)| (square / count - (sum / count).powi(2)).max(0.0))
+            .sum();
+
+        if variance < Self::SOLID_COLOR_VARIANCE {
+            Self::SOLID_COLOR_PENALTY
+        } else {
+            0.0
+        }
     }
+}
 
+impl Heuristic for ImageValidity {
+    fn name(&self) -> &'static str {
+        "image_validity"
+    }
+
+    fn penalty(&self, ctx: &ScoringContext) -> f32 {
+        self.check_image_validity(
+            ctx.generated,
+            ctx.config.validity_samples,
+            ctx.alpha_threshold,
+        )
+    }
+}
+
+/// Penalizes pairs whose keyframes differ a lot, since large motion is harder to interpolate
+pub struct MotionComplexity;
+
+impl MotionComplexity {
     /// Assess motion complexity between source frames
-    fn assess_motion_complexity(&self, source_a: &DynamicImage, source_b: &DynamicImage) -> f32 {
-        let diff = self.calculate_pixel_difference(source_a, source_b);
+    pub fn assess_motion_complexity(
+        &self,
+        source_a: &DynamicImage,
+        source_b: &DynamicImage,
+        samples: usize,
+        alpha_threshold: u8,
+    ) -> f32 {
+        let diff = calculate_pixel_difference(source_a, source_b, samples, alpha_threshold);
 
         // High difference = complex motion = lower confidence
         if diff > 0.4 {
@@ -115,56 +405,39 @@ impl ConfidenceScorer {
             0.0 // Very similar frames, easy to interpolate
         }
     }
+}
 
-    /// Calculate normalized pixel difference between two images
-    fn calculate_pixel_difference(&self, img_a: &DynamicImage, img_b: &DynamicImage) -> f32 {
-        let (w_a, h_a) = img_a.dimensions();
-        let (w_b, h_b) = img_b.dimensions();
-
-        // Different sizes = uncertain
-        if w_a != w_b || h_a != h_b {
-            return 0.5;
-        }
-
-        let rgba_a = img_a.to_rgba8();
-        let rgba_b = img_b.to_rgba8();
-
-        // Sample pixels and calculate difference
-        let total_pixels = (w_a * h_a) as usize;
-        let sample_size = total_pixels.min(500);
-        let step = total_pixels.max(1) / sample_size.max(1);
-
-        let mut total_diff = 0u64;
-        let mut samples = 0u32;
-
-        for (i, (pixel_a, pixel_b)) in rgba_a.pixels().zip(rgba_b.pixels()).enumerate() {
-            if i % step == 0 {
-                // Only compare non-transparent pixels
-                if pixel_a[3] > 128 || pixel_b[3] > 128 {
-                    let diff: u64 = pixel_a
-                        .0
-                        .iter()
-                        .zip(pixel_b.0.iter())
-                        .map(|(a, b)| (i32::from(*a) - i32::from(*b)).unsigned_abs() as u64)
-                        .sum();
-
-                    total_diff += diff;
-                    samples += 1;
-                }
-            }
-        }
+impl Heuristic for MotionComplexity {
+    fn name(&self) -> &'static str {
+        "motion_complexity"
+    }
 
-        if samples == 0 {
-            return 0.0;
-        }
+    fn penalty(&self, ctx: &ScoringContext) -> f32 {
+        self.assess_motion_complexity(
+            ctx.source_a,
+            ctx.source_b,
+            ctx.config.diff_samples,
+            ctx.alpha_threshold,
+        )
+    }
 
-        // Normalize to 0-1 range (max diff per pixel is 255*4=1020)
-        (total_diff as f32) / (samples as f32 * 1020.0)
+    fn needs_generated_frame(&self) -> bool {
+        false
     }
+}
 
+/// Penalizes characters/motion types that have historically been rejected often
+pub struct HistoricalSuccess;
+
+impl HistoricalSuccess {
     /// Check historical success rate from feedback log
-    fn check_historical_success(&self, motion_type: &str, character: Option<&str>) -> f32 {
-        let Some(logger) = &self.feedback_logger else {
+    fn check_historical_success(
+        &self,
+        logger: Option<&FeedbackLogger>,
+        motion_type: &str,
+        character: Option<&str>,
+    ) -> f32 {
+        let Some(logger) = logger else {
             return 0.0;
         };
 
@@ -184,6 +457,30 @@ impl ConfidenceScorer {
             Err(_) => 0.0, // No historical data, assume neutral
         }
     }
+}
+
+impl Heuristic for HistoricalSuccess {
+    fn name(&self) -> &'static str {
+        "historical_success"
+    }
+
+    fn penalty(&self, ctx: &ScoringContext) -> f32 {
+        self.check_historical_success(ctx.feedback, ctx.motion_type, ctx.character)
+    }
+
+    fn needs_generated_frame(&self) -> bool {
+        false
+    }
+}
+
+/// Penalizes frames whose brightness/saturation strays from the source frames
+pub struct ColorConsistency;
+
+impl ColorConsistency {
+    /// ΔE allowed on top of a quarter of the keyframes' own difference
+    const LAB_TOLERANCE: f32 = 10.0;
+    /// Penalty at twice the tolerance and beyond, matching the sRGB check's worst case
+    const MAX_PENALTY: f32 = 0.25;
 
     /// Check color/brightness consistency with source frames
     fn check_color_consistency(
@@ -191,18 +488,23 @@ impl ConfidenceScorer {
         generated: &DynamicImage,
         source_a: &DynamicImage,
         source_b: &DynamicImage,
+        position: f32,
+        stats_cache: &ImageStatsCache,
+        samples: usize,
     ) -> f32 {
-        let gen_stats = self.calculate_image_stats(generated);
-        let a_stats = self.calculate_image_stats(source_a);
-        let b_stats = self.calculate_image_stats(source_b);
+        let gen_stats = stats_cache.stats(generated, samples);
+        let a_stats = stats_cache.stats(source_a, samples);
+        let b_stats = stats_cache.stats(source_b, samples);
 
-        // Expected stats should be roughly between source A and B
-        let expected_brightness = (a_stats.brightness + b_stats.brightness) / 2.0;
-        let expected_saturation = (a_stats.saturation + b_stats.saturation) / 2.0;
+        // Expected stats move from A's to B's as the frame progresses through the sequence
+        let lerp = |a: f32, b: f32| (1.0 - position) * a + position * b;
+        let expected_brightness = lerp(a_stats.brightness, b_stats.brightness);
+        let expected_saturation = lerp(a_stats.saturation, b_stats.saturation);
 
-        // Allow some tolerance (sources might have different lighting)
-        let brightness_tolerance = (a_stats.brightness - b_stats.brightness).abs() + 0.1;
-        let saturation_tolerance = (a_stats.saturation - b_stats.saturation).abs() + 0.1;
+        // Allow some tolerance (the model doesn't change colors linearly), but not so
+        // much that an early frame could look like B
+        let brightness_tolerance = (a_stats.brightness - b_stats.brightness).abs() / 4.0 + 0.1;
+        let saturation_tolerance = (a_stats.saturation - b_stats.saturation).abs() / 4.0 + 0.1;
 
         let brightness_diff = (gen_stats.brightness - expected_brightness).abs();
         let saturation_diff = (gen_stats.saturation - expected_saturation).abs();
@@ -220,76 +522,430 @@ impl ConfidenceScorer {
         penalty
     }
 
-    /// Calculate basic image statistics
-    fn calculate_image_stats(&self, img: &DynamicImage) -> ImageStats {
-        let rgba = img.to_rgba8();
-        let (width, height) = rgba.dimensions();
-        let total_pixels = (width * height) as usize;
-        let sample_size = total_pixels.min(500);
-        let step = total_pixels.max(1) / sample_size.max(1);
+    /// The same check as a perceptual (CIEDE2000) distance between the frame's mean
+    /// color and the keyframes' mean colors blended at `position`. The penalty grows
+    /// with the distance past the tolerance instead of in fixed steps.
+    fn check_lab_consistency(
+        &self,
+        generated: &DynamicImage,
+        source_a: &DynamicImage,
+        source_b: &DynamicImage,
+        position: f32,
+        stats_cache: &ImageStatsCache,
+        samples: usize,
+    ) -> f32 {
+        let [gen_lab, a_lab, b_lab] = [generated, source_a, source_b]
+            .map(|img| color::srgb_to_lab(stats_cache.stats(img, samples).mean_color));
+        let expected: color::Lab =
+            [0, 1, 2].map(|i| (1.0 - position) * a_lab[i] + position * b_lab[i]);
 
-        let mut total_brightness = 0.0f64;
-        let mut total_saturation = 0.0f64;
-        let mut samples = 0u32;
+        let tolerance = color::delta_e_2000(a_lab, b_lab) / 4.0 + Self::LAB_TOLERANCE;
+        let excess = color::delta_e_2000(gen_lab, expected) - tolerance;
+        Self::MAX_PENALTY * (excess / tolerance).clamp(0.0, 1.0)
+    }
+}
 
-        for (i, pixel) in rgba.pixels().enumerate() {
-            if i % step == 0 && pixel[3] > 128 {
-                let r = f64::from(pixel[0]) / 255.0;
-                let g = f64::from(pixel[1]) / 255.0;
-                let b = f64::from(pixel[2]) / 255.0;
-
-                // Brightness (luminance)
-                let brightness = 0.299 * r + 0.587 * g + 0.114 * b;
-                total_brightness += brightness;
-
-                // Saturation
-                let max = r.max(g).max(b);
-                let min = r.min(g).min(b);
-                let saturation = if max > 0.0 {
-                    (max - min) / max
-                } else {
-                    0.0
-                };
-                total_saturation += saturation;
+impl Heuristic for ColorConsistency {
+    fn name(&self) -> &'static str {
+        "color_consistency"
+    }
+
+    fn penalty(&self, ctx: &ScoringContext) -> f32 {
+        let check = match ctx.config.color_space {
+            ColorSpace::Srgb => Self::check_color_consistency,
+            ColorSpace::Lab => Self::check_lab_consistency,
+        };
+        let (from, to, position) = ctx.bracketing_references();
+        check(
+            self,
+            ctx.generated,
+            from,
+            to,
+            position,
+            ctx.stats_cache,
+            ctx.config.stats_samples,
+        )
+    }
+}
+
+/// Penalizes faint duplicate silhouettes ("ghosting"), a common ToonCrafter failure
+pub struct Ghosting;
+
+impl Ghosting {
+    /// Frames are downscaled to at most this size before labeling components
+    const ANALYSIS_SIZE: u32 = 128;
+    /// Coverage (alpha, or darkness on opaque frames) above which a pixel counts as drawn
+    const DRAWN_COVERAGE: u8 = 24;
+    /// Components whose mean coverage is below this are faint
+    const FAINT_COVERAGE: f32 = 128.0;
+
+    /// Penalize faint components in the generated frame beyond what any reference has.
+    /// Moving a solid silhouette doesn't change the count, so normal motion isn't penalized.
+    fn check_ghosting(&self, generated: &DynamicImage, references: &[&DynamicImage]) -> f32 {
+        let expected = references
+            .iter()
+            .map(|reference| self.count_faint_components(reference))
+            .max()
+            .unwrap_or(0);
+        let excess = self
+            .count_faint_components(generated)
+            .saturating_sub(expected);
+
+        match excess {
+            0 => 0.0,
+            1 => 0.15,
+            _ => 0.25,
+        }
+    }
 
-                samples += 1;
+    /// Count 8-connected drawn regions that are faint and large enough not to be noise
+    fn count_faint_components(&self, img: &DynamicImage) -> usize {
+        let small = img.thumbnail(Self::ANALYSIS_SIZE, Self::ANALYSIS_SIZE);
+        let coverage = coverage_map(&small);
+        let (width, height) = (i64::from(small.width()), i64::from(small.height()));
+        let min_area = (coverage.len() / 2000).max(4);
+
+        let mut visited = vec![false; coverage.len()];
+        let mut stack = Vec::new();
+        let mut faint = 0;
+
+        for start in 0..coverage.len() {
+            if visited[start] || coverage[start] <= Self::DRAWN_COVERAGE {
+                continue;
+            }
+
+            // Flood fill one component, summing its coverage
+            visited[start] = true;
+            stack.push(start);
+            let (mut area, mut total) = (0usize, 0u64);
+            while let Some(index) = stack.pop() {
+                area += 1;
+                total += u64::from(coverage[index]);
+
+                let (x, y) = (index as i64 % width, index as i64 / width);
+                for (dx, dy) in NEIGHBORS {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                        continue;
+                    }
+                    let neighbor = (ny * width + nx) as usize;
+                    if !visited[neighbor] && coverage[neighbor] > Self::DRAWN_COVERAGE {
+                        visited[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            if area >= min_area && (total as f32 / area as f32) < Self::FAINT_COVERAGE {
+                faint += 1;
             }
         }
 
-        if samples == 0 {
-            return ImageStats {
-                brightness: 0.5,
-                saturation: 0.0,
-            };
+        faint
+    }
+}
+
+impl Heuristic for Ghosting {
+    fn name(&self) -> &'static str {
+        "ghosting"
+    }
+
+    fn penalty(&self, ctx: &ScoringContext) -> f32 {
+        let mut references = vec![ctx.source_a, ctx.source_b];
+        references.extend(ctx.reference_mid);
+        self.check_ghosting(ctx.generated, &references)
+    }
+}
+
+const NEIGHBORS: [(i64, i64); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// How strongly each pixel is drawn: alpha for transparent frames, darkness for
+/// frames flattened onto a light background
+pub(crate) fn coverage_map(img: &DynamicImage) -> Vec<u8> {
+    let rgba = img.to_rgba8();
+    if is_opaque(img) {
+        rgba.pixels()
+            .map(|pixel| {
+                let luma = (299 * u32::from(pixel[0])
+                    + 587 * u32::from(pixel[1])
+                    + 114 * u32::from(pixel[2]))
+                    / 1000;
+                255 - luma as u8
+            })
+            .collect()
+    } else {
+        rgba.pixels().map(|pixel| pixel[3]).collect()
+    }
+}
+
+/// Calculate normalized pixel difference between two images (0.0 = identical, 1.0 = opposite),
+/// sampling about `samples` pixels and comparing only where either is drawn (alpha at or
+/// above `alpha_threshold`)
+pub fn calculate_pixel_difference(
+    img_a: &DynamicImage,
+    img_b: &DynamicImage,
+    samples: usize,
+    alpha_threshold: u8,
+) -> f32 {
+    let (w_a, h_a) = img_a.dimensions();
+    let (w_b, h_b) = img_b.dimensions();
+
+    // Different sizes = uncertain
+    if w_a != w_b || h_a != h_b {
+        return 0.5;
+    }
+
+    // Every pixel of a grayscale frame is opaque, so alpha says nothing about content
+    if is_grayscale(img_a) && is_grayscale(img_b) {
+        return calculate_luminance_difference(img_a, img_b, samples);
+    }
+
+    let rgba_a = img_a.to_rgba8();
+    let rgba_b = img_b.to_rgba8();
+
+    // Sample pixels and calculate difference
+    let total_pixels = (w_a * h_a) as usize;
+    let sample_size = total_pixels.min(samples);
+    let step = total_pixels.max(1) / sample_size.max(1);
+
+    let mut total_diff = 0u64;
+    let mut counted = 0u32;
+
+    for (i, (pixel_a, pixel_b)) in rgba_a.pixels().zip(rgba_b.pixels()).enumerate() {
+        if i % step == 0 {
+            // Only compare non-transparent pixels
+            if pixel_a[3] >= alpha_threshold || pixel_b[3] >= alpha_threshold {
+                let diff: u64 = pixel_a
+                    .0
+                    .iter()
+                    .zip(pixel_b.0.iter())
+                    .map(|(a, b)| u64::from((i32::from(*a) - i32::from(*b)).unsigned_abs()))
+                    .sum();
+
+                total_diff += diff;
+                counted += 1;
+            }
         }
+    }
+
+    if counted == 0 {
+        return 0.0;
+    }
+
+    // Normalize to 0-1 range (max diff per pixel is 255*4=1020)
+    (total_diff as f32) / (counted as f32 * 1020.0)
+}
+
+/// Mean luminance difference between two same-sized images (0.0-1.0), sampling about
+/// `samples` pixels
+fn calculate_luminance_difference(
+    img_a: &DynamicImage,
+    img_b: &DynamicImage,
+    samples: usize,
+) -> f32 {
+    let luma_a = img_a.to_luma8();
+    let luma_b = img_b.to_luma8();
+    let total_pixels = luma_a.as_raw().len();
+    let step = (total_pixels / samples.max(1)).max(1);
+
+    let mut total_diff = 0u64;
+    let mut counted = 0u32;
+    for (a, b) in luma_a.pixels().zip(luma_b.pixels()).step_by(step) {
+        total_diff += u64::from(a[0].abs_diff(b[0]));
+        counted += 1;
+    }
+
+    if counted == 0 {
+        return 0.0;
+    }
+
+    (total_diff as f32) / (counted as f32 * 255.0)
+}
+
+/// Calculate basic image statistics over the drawn pixels (alpha at or above
+/// `alpha_threshold`), sampling about `samples` pixels
+pub fn calculate_image_stats(
+    img: &DynamicImage,
+    samples: usize,
+    alpha_threshold: u8,
+) -> ImageStats {
+    rgba_stats(&img.to_rgba8(), samples, alpha_threshold)
+}
+
+/// [`calculate_image_stats`] on pixels already converted to RGBA
+pub(crate) fn rgba_stats(rgba: &RgbaImage, samples: usize, alpha_threshold: u8) -> ImageStats {
+    let (width, height) = rgba.dimensions();
+    let total_pixels = (width * height) as usize;
+    let sample_size = total_pixels.min(samples);
+    let step = total_pixels.max(1) / sample_size.max(1);
+
+    let mut total_brightness = 0.0f64;
+    let mut total_saturation = 0.0f64;
+    let mut total_color = [0.0f64; 3];
+    let mut counted = 0u32;
+
+    for (i, pixel) in rgba.pixels().enumerate() {
+        if i % step == 0 && pixel[3] >= alpha_threshold {
+            let r = f64::from(pixel[0]) / 255.0;
+            let g = f64::from(pixel[1]) / 255.0;
+            let b = f64::from(pixel[2]) / 255.0;
+
+            // Brightness (luminance)
+            let brightness = 0.299 * r + 0.587 * g + 0.114 * b;
+            total_brightness += brightness;
+
+            // Saturation
+            let max = r.max(g).max(b);
+            let min = r.min(g).min(b);
+            let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+            total_saturation += saturation;
+
+            for (total, channel) in total_color.iter_mut().zip([r, g, b]) {
+                *total += channel;
+            }
+            counted += 1;
+        }
+    }
+
+    if counted == 0 {
+        return ImageStats {
+            brightness: 0.5,
+            saturation: 0.0,
+            mean_color: [0.5; 3],
+        };
+    }
+
+    ImageStats {
+        brightness: (total_brightness / f64::from(counted)) as f32,
+        saturation: (total_saturation / f64::from(counted)) as f32,
+        mean_color: total_color.map(|total| (total / f64::from(counted)) as f32),
+    }
+}
 
-        ImageStats {
-            brightness: (total_brightness / f64::from(samples)) as f32,
-            saturation: (total_saturation / f64::from(samples)) as f32,
+/// Mean brightness, saturation and sRGB color of an image's opaque pixels, each 0.0-1.0
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ImageStats {
+    pub brightness: f32,
+    pub saturation: f32,
+    pub mean_color: [f32; 3],
+}
+
+/// Check whether an image has no transparency at all (no alpha channel, or every pixel opaque)
+pub fn is_opaque(img: &DynamicImage) -> bool {
+    if !img.color().has_alpha() {
+        return true;
+    }
+
+    img.to_rgba8().pixels().all(|pixel| pixel[3] == 255)
+}
+
+/// Bounding box `(x, y, width, height)` of an image's drawn content: pixels with some
+/// alpha, or on opaque frames some darkness. `None` if nothing is drawn.
+pub fn content_bounds(img: &DynamicImage) -> Option<(u32, u32, u32, u32)> {
+    /// Coverage above which a pixel counts as drawn, ignoring faint anti-aliasing
+    const DRAWN_COVERAGE: u8 = 24;
+
+    let width = img.width() as usize;
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+    for (i, coverage) in coverage_map(img).into_iter().enumerate() {
+        if coverage > DRAWN_COVERAGE {
+            let (x, y) = (i % width, i / width);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
         }
     }
+
+    (min_x <= max_x).then(|| {
+        (
+            min_x as u32,
+            min_y as u32,
+            (max_x - min_x + 1) as u32,
+            (max_y - min_y + 1) as u32,
+        )
+    })
 }
 
-#[derive(Debug)]
-struct ImageStats {
-    brightness: f32,
-    saturation: f32,
+/// How far the aspect ratio of a generated frame's content falls outside the range
+/// spanned by the keyframes' content, as a fraction (0.0 = within the range, 0.5 = 50%
+/// wider or taller than either keyframe). `None` if any of the three is blank.
+///
+/// A model that ignores the square padding and fills the whole canvas comes back
+/// stretched once the frame is restored to the original size, which shows up here.
+pub fn aspect_deviation(
+    generated: &DynamicImage,
+    source_a: &DynamicImage,
+    source_b: &DynamicImage,
+) -> Option<f32> {
+    let aspect = |img: &DynamicImage| {
+        content_bounds(img).map(|(_, _, width, height)| width as f32 / height as f32)
+    };
+    let (generated, a, b) = (aspect(generated)?, aspect(source_a)?, aspect(source_b)?);
+
+    let (low, high) = (a.min(b), a.max(b));
+    Some(if generated < low {
+        low / generated - 1.0
+    } else if generated > high {
+        generated / high - 1.0
+    } else {
+        0.0
+    })
+}
+
+/// Check whether an image is an opaque grayscale frame: a luma color type, or RGB(A)
+/// with equal channels everywhere. Such frames carry their content in luminance; line
+/// art on transparency is not grayscale even when it is drawn in black.
+pub fn is_grayscale(img: &DynamicImage) -> bool {
+    if !is_opaque(img) {
+        return false;
+    }
+
+    match img.color() {
+        ColorType::L8 | ColorType::La8 | ColorType::L16 | ColorType::La16 => true,
+        _ => img
+            .to_rgba8()
+            .pixels()
+            .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2]),
+    }
 }
 
 /// Detect motion type from two frames
-pub fn detect_motion_type(img_a: &DynamicImage, img_b: &DynamicImage) -> String {
-    let scorer = ConfidenceScorer::new(0.85);
-    let diff = scorer.calculate_pixel_difference(img_a, img_b);
+pub fn detect_motion_type(
+    img_a: &DynamicImage,
+    img_b: &DynamicImage,
+    thresholds: &MotionThresholds,
+    alpha_threshold: u8,
+) -> String {
+    let diff = calculate_pixel_difference(
+        img_a,
+        img_b,
+        ConfidenceConfig::default().diff_samples,
+        alpha_threshold,
+    );
+    classify_motion(diff, thresholds).to_string()
+}
 
+/// Label a keyframe pixel difference with a motion type
+pub fn classify_motion(diff: f32, thresholds: &MotionThresholds) -> &'static str {
     // Very rough heuristics - in practice you'd want more sophisticated detection
-    if diff < 0.05 {
-        "static".to_string()
-    } else if diff < 0.15 {
-        "subtle".to_string() // Small movements like breathing, blinking
-    } else if diff < 0.3 {
-        "normal".to_string() // Typical animation motion
+    if diff < thresholds.static_max {
+        "static"
+    } else if diff < thresholds.subtle_max {
+        "subtle" // Small movements like breathing, blinking
+    } else if diff < thresholds.normal_max {
+        "normal" // Typical animation motion
     } else {
-        "dynamic".to_string() // Large movements, action scenes
+        "dynamic" // Large movements, action scenes
     }
 }
 
@@ -321,10 +977,218 @@ mod tests {
         let img_b = DynamicImage::new_rgba8(100, 100);
 
         // Identical images should be detected as static
-        let motion = detect_motion_type(&img_a, &img_b);
+        let motion = detect_motion_type(
+            &img_a,
+            &img_b,
+            &MotionThresholds::default(),
+            DEFAULT_ALPHA_THRESHOLD,
+        );
         assert!(motion == "static" || motion == "subtle");
     }
 
+    #[test]
+    fn test_custom_motion_thresholds() {
+        assert_eq!(classify_motion(0.2, &MotionThresholds::default()), "normal");
+
+        let relaxed = MotionThresholds {
+            static_max: 0.1,
+            subtle_max: 0.35,
+            normal_max: 0.6,
+        };
+        assert_eq!(classify_motion(0.2, &relaxed), "subtle");
+        assert_eq!(classify_motion(0.05, &relaxed), "static");
+        assert_eq!(classify_motion(0.7, &relaxed), "dynamic");
+    }
+
+    #[test]
+    fn test_opaque_validity() {
+        let gradient = DynamicImage::ImageRgb8(image::RgbImage::from_fn(100, 100, |x, _| {
+            let v = (x * 255 / 99) as u8;
+            image::Rgb([v, v, v])
+        }));
+        let flat_gray = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            100,
+            100,
+            image::Rgb([128, 128, 128]),
+        ));
+
+        assert!(is_opaque(&gradient));
+        assert!(is_opaque(&flat_gray));
+        assert!(!is_opaque(&DynamicImage::new_rgba8(10, 10)));
+
+        assert!(
+            ImageValidity
+                .check_image_validity(&gradient, 1000, DEFAULT_ALPHA_THRESHOLD)
+                .abs()
+                < f32::EPSILON
+        );
+        assert!(
+            ImageValidity.check_image_validity(&flat_gray, 1000, DEFAULT_ALPHA_THRESHOLD) > 0.0
+        );
+    }
+
+    #[test]
+    fn test_custom_heuristic() {
+        struct AlwaysPenalize;
+
+        impl Heuristic for AlwaysPenalize {
+            fn name(&self) -> &'static str {
+                "always_penalize"
+            }
+
+            fn penalty(&self, _ctx: &ScoringContext) -> f32 {
+                0.2
+            }
+        }
+
+        let img = DynamicImage::new_rgba8(50, 50);
+        let mut scorer = ConfidenceScorer::new(0.85);
+        let before = scorer
+            .score_frame_breakdown(&img, &img, &img, "walk", None, 0.5)
+            .unwrap();
+
+        scorer.add_heuristic(AlwaysPenalize);
+        let after = scorer
+            .score_frame_breakdown(&img, &img, &img, "walk", None, 0.5)
+            .unwrap();
+
+        let names: Vec<&str> = after.penalties.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "image_validity",
+                "motion_complexity",
+                "historical_success",
+                "color_consistency",
+                "ghosting",
+                "always_penalize"
+            ]
+        );
+        assert!((before.score - after.score - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_color_reference_follows_position() {
+        let gray = |v: u8| {
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(20, 20, image::Rgb([v, v, v])))
+        };
+        let dark_a = gray(25);
+        let light_b = gray(230);
+
+        // First of four inbetweens, still looking like A
+        let early = dark_a.clone();
+        let cache = ImageStatsCache::new(0);
+        let penalty_at = |position: f32| {
+            ColorConsistency
+                .check_color_consistency(&early, &dark_a, &light_b, position, &cache, 500)
+        };
+
+        assert!(penalty_at(0.2).abs() < f32::EPSILON);
+        assert!(penalty_at(0.5) > 0.0);
+
+        // The same frame late in the sequence has the wrong colors
+        assert!(penalty_at(0.8) > 0.0);
+    }
+
+    #[test]
+    fn test_lab_penalty_follows_perceived_difference() {
+        let solid = |rgb: [u8; 3]| {
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(20, 20, image::Rgb(rgb)))
+        };
+        let gray = solid([128, 128, 128]);
+        // Both 30 levels from gray in RGB, but green is much further by eye (ΔE 18.6 vs 13.0)
+        let redder = solid([158, 128, 128]);
+        let greener = solid([128, 158, 128]);
+
+        let cache = ImageStatsCache::new(0);
+        let srgb = |frame: &DynamicImage| {
+            ColorConsistency.check_color_consistency(frame, &gray, &gray, 0.5, &cache, 500)
+        };
+        let lab = |frame: &DynamicImage| {
+            ColorConsistency.check_lab_consistency(frame, &gray, &gray, 0.5, &cache, 500)
+        };
+
+        assert!((srgb(&redder) - srgb(&greener)).abs() < f32::EPSILON);
+        assert!(lab(&gray).abs() < f32::EPSILON);
+        assert!(lab(&redder) > 0.0);
+        assert!(lab(&greener) > lab(&redder) + 0.1);
+        assert!(lab(&greener) < ColorConsistency::MAX_PENALTY);
+    }
+
+    /// 64x64 transparent frame with a solid 16x16 square at `x` and, optionally, a faint
+    /// copy at `ghost_x`
+    fn silhouette(x: u32, ghost_x: Option<u32>) -> DynamicImage {
+        let mut img = image::RgbaImage::new(64, 64);
+        for (px, py, pixel) in img.enumerate_pixels_mut() {
+            if (24..40).contains(&py) {
+                if (x..x + 16).contains(&px) {
+                    *pixel = image::Rgba([20, 20, 20, 255]);
+                } else if ghost_x.is_some_and(|g| (g..g + 16).contains(&px)) {
+                    *pixel = image::Rgba([20, 20, 20, 60]);
+                }
+            }
+        }
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn test_ghosting_clean_frame() {
+        let source_a = silhouette(4, None);
+        let source_b = silhouette(44, None);
+
+        // A single silhouette partway through the motion is fine
+        let clean = silhouette(24, None);
+        assert!(
+            Ghosting
+                .check_ghosting(&clean, &[&source_a, &source_b])
+                .abs()
+                < f32::EPSILON
+        );
+    }
+
+    #[test]
+    fn test_ghosting_faint_copy_penalized() {
+        let source_a = silhouette(4, None);
+        let source_b = silhouette(44, None);
+
+        let ghosted = silhouette(24, Some(46));
+        assert!(Ghosting.check_ghosting(&ghosted, &[&source_a, &source_b]) > 0.0);
+
+        // Flattened onto white, the faint copy shows up as light gray instead
+        let flatten = |img: DynamicImage| {
+            let mut flat = image::RgbImage::from_pixel(64, 64, image::Rgb([255; 3]));
+            for (px, py, pixel) in img.to_rgba8().enumerate_pixels() {
+                let alpha = u16::from(pixel[3]);
+                let value = (u16::from(pixel[0]) * alpha + 255 * (255 - alpha)) / 255;
+                flat.put_pixel(px, py, image::Rgb([value as u8; 3]));
+            }
+            DynamicImage::ImageRgb8(flat)
+        };
+        let (flat_a, flat_b) = (flatten(source_a), flatten(source_b));
+        assert!(
+            Ghosting
+                .check_ghosting(&flatten(silhouette(24, None)), &[&flat_a, &flat_b])
+                .abs()
+                < f32::EPSILON
+        );
+        assert!(Ghosting.check_ghosting(&flatten(ghosted), &[&flat_a, &flat_b]) > 0.0);
+    }
+
+    #[test]
+    fn test_predict_source_penalties() {
+        let scorer = ConfidenceScorer::new(0.85);
+        let black = DynamicImage::ImageRgb8(image::RgbImage::new(20, 20));
+        let white =
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(20, 20, image::Rgb([255; 3])));
+
+        let penalties = scorer.predict_source_penalties(&black, &white, "dynamic", None);
+        let names: Vec<_> = penalties.iter().map(|(name, _)| name.as_str()).collect();
+
+        // Validity and color consistency need a generated frame
+        assert_eq!(names, vec!["motion_complexity", "historical_success"]);
+        assert!((penalties[0].1 - 0.35).abs() < f32::EPSILON);
+    }
+
     #[test]
     fn test_auto_accept_threshold() {
         let scorer = ConfidenceScorer::new(0.85);
@@ -334,4 +1198,206 @@ mod tests {
         assert!(!scorer.should_auto_accept(0.84));
         assert!(!scorer.should_auto_accept(0.5));
     }
+
+    #[test]
+    fn test_sample_size_keeps_scores_stable() {
+        let gradient = |offset: u32| {
+            DynamicImage::ImageRgba8(image::RgbaImage::from_fn(256, 256, |x, y| {
+                let value = ((x + y + offset) % 256) as u8;
+                image::Rgba([value, 255 - value, 128, 255])
+            }))
+        };
+        let (img_a, img_b, generated) = (gradient(0), gradient(64), gradient(32));
+
+        let score_with = |samples: usize| {
+            let config = ConfidenceConfig {
+                validity_samples: samples,
+                diff_samples: samples,
+                stats_samples: samples,
+                ..ConfidenceConfig::default()
+            };
+            ConfidenceScorer::new(0.85)
+                .with_config(config)
+                .score_frame(&generated, &img_a, &img_b, "walk", None)
+                .unwrap()
+        };
+
+        let baseline = score_with(500);
+        for samples in [100, 5000, 1_000_000] {
+            let score = score_with(samples);
+            assert!((0.0..=1.0).contains(&score));
+            assert!(
+                (score - baseline).abs() < 0.2,
+                "{samples} samples: {score} vs {baseline}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_grayscale_frames_use_luminance() {
+        let gradient = DynamicImage::ImageLuma8(image::GrayImage::from_fn(64, 64, |x, _| {
+            image::Luma([(x * 4) as u8])
+        }));
+        let blank =
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(64, 64, image::Luma([128])));
+
+        assert!(is_grayscale(&gradient));
+        assert!(is_grayscale(&DynamicImage::ImageRgba8(blank.to_rgba8())));
+        // Black line art on transparency is alpha-driven
+        let mut line_art = image::RgbaImage::new(64, 64);
+        line_art.put_pixel(3, 3, image::Rgba([0, 0, 0, 255]));
+        assert!(!is_grayscale(&DynamicImage::ImageRgba8(line_art)));
+
+        assert!(
+            ImageValidity
+                .check_image_validity(&gradient, 1000, DEFAULT_ALPHA_THRESHOLD)
+                .abs()
+                < f32::EPSILON
+        );
+        assert!(ImageValidity.check_image_validity(&blank, 1000, DEFAULT_ALPHA_THRESHOLD) > 0.0);
+
+        // The luminance difference spans the full 0-1 range rather than topping out at 0.75
+        let dark = DynamicImage::ImageLuma8(image::GrayImage::new(64, 64));
+        let light =
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(64, 64, image::Luma([255])));
+        assert!(
+            (calculate_pixel_difference(&dark, &light, 500, DEFAULT_ALPHA_THRESHOLD) - 1.0).abs()
+                < f32::EPSILON
+        );
+        assert!(calculate_pixel_difference(&gradient, &blank, 500, DEFAULT_ALPHA_THRESHOLD) > 0.1);
+    }
+
+    #[test]
+    fn test_solid_color_frames_penalized() {
+        let magenta = |alpha: u8| {
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+                64,
+                64,
+                image::Rgba([255, 0, 255, alpha]),
+            ))
+        };
+        let solid = ImageValidity::SOLID_COLOR_PENALTY;
+
+        // Crashed outputs, with and without full opacity
+        assert!(
+            (ImageValidity.check_image_validity(&magenta(255), 1000, DEFAULT_ALPHA_THRESHOLD)
+                - solid)
+                .abs()
+                < 1e-6
+        );
+        assert!(
+            (ImageValidity.check_image_validity(&magenta(250), 1000, DEFAULT_ALPHA_THRESHOLD)
+                - solid)
+                .abs()
+                < 1e-6
+        );
+        let black = DynamicImage::ImageRgb8(image::RgbImage::new(64, 64));
+        assert!(
+            (ImageValidity.check_image_validity(&black, 1000, DEFAULT_ALPHA_THRESHOLD) - solid)
+                .abs()
+                < 1e-6
+        );
+
+        // Simple but not uniform: two flat tones
+        let two_tone = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, _| {
+            if x < 40 {
+                image::Rgba([255, 0, 255, 250])
+            } else {
+                image::Rgba([255, 255, 255, 250])
+            }
+        }));
+        assert!(
+            ImageValidity
+                .check_image_validity(&two_tone, 1000, DEFAULT_ALPHA_THRESHOLD)
+                .abs()
+                < f32::EPSILON
+        );
+
+        // Black strokes covering most of a transparent frame vary in alpha
+        let strokes = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |_, y| {
+            image::Rgba([0, 0, 0, if y % 4 == 0 { 0 } else { 255 }])
+        }));
+        assert!(
+            ImageValidity
+                .check_image_validity(&strokes, 1000, DEFAULT_ALPHA_THRESHOLD)
+                .abs()
+                < f32::EPSILON
+        );
+    }
+
+    #[test]
+    fn test_keyframe_stats_are_cached() {
+        let scorer = ConfidenceScorer::new(0.85);
+        let keyframe = |v: u8| {
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(20, 20, image::Rgba([v, v, v, 255])))
+        };
+        let (held, b1, b2) = (keyframe(40), keyframe(120), keyframe(200));
+
+        scorer
+            .score_frame(&keyframe(80), &held, &b1, "walk", None)
+            .unwrap();
+        assert_eq!(scorer.stats_cache().hits(), 0);
+
+        // Frame A is held into the next pair; a copy with equal pixels is recognized
+        scorer
+            .score_frame(&keyframe(160), &held.clone(), &b2, "walk", None)
+            .unwrap();
+        assert_eq!(scorer.stats_cache().hits(), 1);
+        assert_eq!(scorer.stats_cache().misses(), 5);
+    }
+
+    #[test]
+    fn test_reference_mid_sets_expected_color() {
+        let gray = |v: u8| {
+            DynamicImage::ImageRgb8(image::RgbImage::from_pixel(20, 20, image::Rgb([v, v, v])))
+        };
+        // A flash: both keyframes dark, the middle of the motion bright
+        let (dark_a, dark_b, bright) = (gray(25), gray(25), gray(230));
+        let scorer = ConfidenceScorer::new(0.85);
+        let color_penalty = |mid: Option<&DynamicImage>, position: f32| {
+            let references = References {
+                source_a: &dark_a,
+                source_b: &dark_b,
+                mid,
+            };
+            let breakdown = scorer
+                .score_against(&bright, references, "walk", None, position)
+                .unwrap();
+            breakdown
+                .penalties
+                .iter()
+                .find(|(name, _)| name == "color_consistency")
+                .unwrap()
+                .1
+        };
+
+        assert!(color_penalty(None, 0.5) > 0.0);
+        assert!(color_penalty(Some(&bright), 0.5).abs() < f32::EPSILON);
+        // Near a keyframe the frame should still look like it
+        assert!(color_penalty(Some(&bright), 0.05) > 0.0);
+    }
+
+    #[test]
+    fn test_lower_alpha_threshold_counts_washes() {
+        // A semi-transparent wash: varied color, but no pixel reaches the default cutoff
+        let wash = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([(x * 4) as u8, (y * 4) as u8, 200, 100])
+        }));
+        let validity = |scorer: ConfidenceScorer| {
+            let breakdown = scorer
+                .score_frame_breakdown(&wash, &wash, &wash, "walk", None, 0.5)
+                .unwrap();
+            breakdown
+                .penalties
+                .iter()
+                .find(|(name, _)| name == "image_validity")
+                .unwrap()
+                .1
+        };
+
+        assert!(validity(ConfidenceScorer::new(0.85)) > 0.0);
+        assert!(
+            validity(ConfidenceScorer::new(0.85).with_alpha_threshold(64)).abs() < f32::EPSILON
+        );
+    }
 }
@@ -1,5 +1,10 @@
+use crate::api::configured_get;
+use crate::confidence::DEFAULT_ALPHA_THRESHOLD;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,42 +17,170 @@ pub enum ConfigError {
 
     #[error("Failed to serialize config: {0}")]
     SerializeError(#[from] toml::ser::Error),
+
+    #[error("Failed to parse config: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Failed to fetch config from {url}: {message}")]
+    FetchError { url: String, message: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     /// Confidence threshold for auto-accepting frames (0.0 - 1.0)
     pub auto_accept_threshold: f32,
 
+    /// Frames scoring below this are auto-rejected rather than left for review
+    /// (0.0 - 1.0, below `auto_accept_threshold`; disabled if None)
+    #[serde(default)]
+    pub reject_threshold: Option<f32>,
+
     /// Path to feedback log file (optional, uses default if None)
     pub feedback_log_path: Option<String>,
 
+    /// Take an advisory file lock around each feedback log write (and a shared one
+    /// for reads), so several processes can share a log without tearing lines.
+    /// Turn off where locking isn't supported and blocks instead of failing.
+    #[serde(default = "default_lock_feedback_log")]
+    pub lock_feedback_log: bool,
+
+    /// Directory caching API results, so regenerating an identical request is free
+    /// (caching disabled if None)
+    pub cache_dir: Option<String>,
+
+    /// Flag generated frames whose perceptual hash is within this many bits of a
+    /// keyframe's as near-duplicates (check disabled if None)
+    #[serde(default = "default_near_duplicate_distance")]
+    pub near_duplicate_distance: Option<u32>,
+
+    /// Treat a generated frame whose perceptual hash is within this many bits of the
+    /// frame before it as a repeat (check disabled if None)
+    #[serde(default)]
+    pub repeated_frame_distance: Option<u32>,
+
+    /// Drop repeated frames instead of flagging them for review. The remaining frames
+    /// keep their original positions between the keyframes.
+    #[serde(default)]
+    pub drop_repeated_frames: bool,
+
+    /// When the model renders fewer frames than requested, blend neighboring frames
+    /// into the missing evenly spaced positions instead of returning fewer. Blended
+    /// frames are marked `synthesized`.
+    #[serde(default)]
+    pub upsample_when_short: bool,
+
+    /// Keep only the shape (alpha) of generated frames and recolor them from the
+    /// keyframes, for flat-colored line art where the model's colors drift
+    #[serde(default)]
+    pub interpolate_alpha_only: bool,
+
+    /// Flag frames whose content's aspect ratio falls outside the keyframes' by more
+    /// than this fraction (e.g. 0.25) as possibly stretched (check disabled if None)
+    #[serde(default)]
+    pub aspect_tolerance: Option<f32>,
+
+    /// Most inbetweens one generation may ask for, guarding against typos like
+    /// `--num-frames 10000` that would hammer the API
+    #[serde(default = "default_max_num_frames")]
+    pub max_num_frames: u32,
+
     /// API configuration
     pub api: ApiConfig,
 
     /// Preprocessing options
     pub preprocessing: PreprocessingConfig,
+
+    /// Confidence scoring options
+    #[serde(default)]
+    pub confidence: ConfidenceConfig,
+
+    /// Cutoffs for the auto-detected motion type
+    #[serde(default)]
+    pub motion_thresholds: MotionThresholds,
+
+    /// Regenerating sequences the model clearly botched
+    #[serde(default)]
+    pub retry_on_low_confidence: RetryOnLowConfidence,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiConfig {
-    /// Backend type: "replicate", "local", "serverless"
+    /// Backend type: "replicate", "local", "serverless", or "crossfade" (offline
+    /// placeholder frames blended from the keyframes, for demos and tests)
     pub backend: String,
 
     /// API endpoint URL (for local/serverless backends)
     pub endpoint: String,
 
-    /// API key (required for Replicate)
+    /// API key (required for Replicate). `file:<path>` reads it from a file and
+    /// `env:<VAR>` from an environment variable, keeping the key out of this file.
     pub api_key: Option<String>,
 
-    /// Replicate model version (for Replicate backend)
+    /// File containing the API key, e.g. a mounted secret (takes precedence over
+    /// `api_key`; the `REPLICATE_API_KEY` env var still wins over both)
+    pub api_key_file: Option<PathBuf>,
+
+    /// Replicate model to run, as `owner/name:hash` (Replicate backend only;
+    /// defaults to the pinned ToonCrafter version)
     pub replicate_model: Option<String>,
 
     /// Style strength (0.0 - 1.0)
     pub style_strength: f32,
 
-    /// Request timeout in seconds
-    pub timeout_secs: u64,
+    /// Ask for grayscale inbetweens. Backends that can't produce them have their frames
+    /// converted to luminance (alpha is kept).
+    #[serde(default)]
+    pub grayscale: bool,
+
+    /// Overall time to wait for a generation, including Replicate polling, in seconds.
+    /// `timeout_secs` is accepted as a deprecated alias.
+    #[serde(alias = "timeout_secs")]
+    pub total_timeout_secs: u64,
+
+    /// Timeout for each individual HTTP call (Replicate calls and output downloads), in
+    /// seconds
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+
+    /// Seconds between Replicate status polls
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+
+    /// Longest base64 data URI a keyframe may be sent to Replicate as. Larger keyframes
+    /// are downscaled until they fit, rather than failing with an opaque 400.
+    #[serde(default = "default_max_data_uri_bytes")]
+    pub max_data_uri_bytes: usize,
+
+    /// Number of inbetweens the model generates internally (backend default if None).
+    /// The model's output is evenly sampled down to the requested `num_frames`, so set
+    /// this equal to `num_frames` to get the model's frames back without resampling.
+    /// Replicate only enables ToonCrafter's 2x interpolation when this exceeds its
+    /// native 14 inbetweens.
+    pub native_frame_count: Option<u32>,
+
+    /// Extra model-specific inputs (a table, e.g. `{ fps = 8 }`) merged into the
+    /// Replicate request over the built-in fields. Ignored by other backends.
+    pub extra_inputs: Option<serde_json::Value>,
+
+    /// HTTP proxy for all API and download requests, as `[http://][user[:password]@]host[:port]`
+    pub proxy: Option<String>,
+
+    /// PEM file of extra root certificates for HTTPS (e.g. a corporate proxy's CA), trusted
+    /// alongside the system roots. Applied process-wide through `SSL_CERT_FILE` by
+    /// [`crate::api::install_ca_bundle`], so it only affects OpenSSL-based builds (Linux).
+    pub ca_bundle: Option<String>,
+
+    /// Scratch directory for downloaded videos and extracted frames (system temp if None).
+    /// Point this at real disk on machines whose temp is a small tmpfs.
+    pub temp_dir: Option<PathBuf>,
+
+    /// Copy every frame extracted from the model's video here, before they are sampled
+    /// down to `num_frames` (for debugging or picking frames by hand)
+    pub native_frames_dir: Option<PathBuf>,
+
+    /// `User-Agent` header for all outbound requests (`gp_inbetween/<version>` if None)
+    pub user_agent: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,49 +196,633 @@ pub struct PreprocessingConfig {
 
     /// Minimum stroke length in pixels (strokes shorter than this are removed)
     pub min_stroke_length: f32,
+
+    /// Snap cleaned-up alpha to fully transparent or fully opaque. Turn off to keep
+    /// anti-aliased stroke edges; isolated noise pixels are still removed.
+    #[serde(default = "default_binarize_alpha")]
+    pub binarize_alpha: bool,
+
+    /// Fade the content's alpha to zero over this many pixels where it meets the
+    /// normalization padding, softening the seam the model sees (0 = off). Opaque
+    /// grayscale frames are padded with their own paper and are left alone.
+    #[serde(default)]
+    pub feather_radius: u32,
+
+    /// Key the paper of opaque scans out to transparency before anything else, so the
+    /// alpha-based cleanup and heuristics see the line art (off if None)
+    #[serde(default)]
+    pub remove_background: Option<BackgroundKey>,
+
+    /// Cleanup erases opaque pixels with fewer opaque neighbors than this as noise.
+    /// Lower it to 1 for thin 1px line art, whose line ends only have one neighbor.
+    #[serde(default = "default_min_neighbors")]
+    pub min_neighbors: u32,
+
+    /// Which surrounding pixels count as neighbors for `min_neighbors`
+    #[serde(default)]
+    pub connectivity: Connectivity,
+
+    /// Alpha at or above which a pixel counts as drawn, in cleanup and in every
+    /// scoring heuristic. Lower it for line art with semi-transparent washes.
+    #[serde(default = "default_alpha_threshold")]
+    pub alpha_threshold: u8,
+}
+
+/// Neighborhood used by cleanup's noise removal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Connectivity {
+    /// Only the pixels sharing an edge
+    Four,
+    /// Edges and corners
+    #[default]
+    Eight,
+}
+
+impl Connectivity {
+    /// Number of neighbors a pixel has away from the image border
+    pub fn neighbors(self) -> u32 {
+        match self {
+            Self::Four => 4,
+            Self::Eight => 8,
+        }
+    }
+}
+
+/// Which pixels `remove_background` makes transparent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackgroundKey {
+    /// Paper color, as `"#rrggbb"` or `"auto"` to sample the image's corners
+    pub color: KeyColor,
+
+    /// Largest per-channel difference from the paper color that is keyed out fully.
+    /// Pixels up to twice as far are faded partway, keeping anti-aliased edges.
+    pub tolerance: u8,
+}
+
+impl Default for BackgroundKey {
+    fn default() -> Self {
+        Self {
+            color: KeyColor::Auto,
+            tolerance: 24,
+        }
+    }
+}
+
+/// Paper color of a [`BackgroundKey`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum KeyColor {
+    /// Average of the image's four corner pixels
+    Auto,
+    Rgb([u8; 3]),
+}
+
+impl TryFrom<String> for KeyColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value == "auto" {
+            return Ok(Self::Auto);
+        }
+        let hex = value.strip_prefix('#').unwrap_or(&value);
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|part| u8::from_str_radix(part, 16).ok())
+        };
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Self::Rgb([r, g, b])),
+            _ => Err(format!(
+                "expected \"auto\" or a color like \"#f5f0e6\", got \"{value}\""
+            )),
+        }
+    }
+}
+
+impl From<KeyColor> for String {
+    fn from(color: KeyColor) -> Self {
+        match color {
+            KeyColor::Auto => "auto".to_string(),
+            KeyColor::Rgb([r, g, b]) => format!("#{r:02x}{g:02x}{b:02x}"),
+        }
+    }
+}
+
+fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Where the last config fetched from `url` is kept: the user's own cache directory,
+/// as the config can hold an API key
+fn url_cache_path(url: &str) -> Option<PathBuf> {
+    let mut name = String::with_capacity(64);
+    for byte in Sha256::digest(url.as_bytes()) {
+        let _ = write!(name, "{byte:02x}");
+    }
+    dirs::cache_dir().map(|dir| {
+        dir.join("gp_inbetween")
+            .join("config")
+            .join(format!("{name}.toml"))
+    })
+}
+
+/// Write `contents` to `path` readable by the current user only
+pub(crate) fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // `mode` only applies when the file is created
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents.as_ref())
+}
+
+/// Fetch through the proxy and timeout in `api`
+fn fetch_config(url: &str, api: &ApiConfig) -> Result<Config, ConfigError> {
+    let fetch_error = |message: String| ConfigError::FetchError {
+        url: url.to_string(),
+        message,
+    };
+    let response = configured_get(api, url)
+        .map_err(|e| fetch_error(e.to_string()))?
+        .send()
+        .map_err(|e| fetch_error(e.to_string()))?;
+    if !(200..300).contains(&response.status_code) {
+        return Err(fetch_error(format!(
+            "HTTP {} {}",
+            response.status_code, response.reason_phrase
+        )));
+    }
+
+    let body = response.as_str().map_err(|e| fetch_error(e.to_string()))?;
+    let is_json = response
+        .headers
+        .get("content-type")
+        .is_some_and(|content_type| content_type.contains("json"))
+        || Path::new(url.split(['?', '#']).next().unwrap_or(url))
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    if is_json {
+        Ok(serde_json::from_str(body)?)
+    } else {
+        Ok(toml::from_str(body)?)
+    }
+}
+
+fn default_max_num_frames() -> u32 {
+    64
+}
+
+fn default_min_neighbors() -> u32 {
+    2
+}
+
+fn default_binarize_alpha() -> bool {
+    true
+}
+
+fn default_alpha_threshold() -> u8 {
+    DEFAULT_ALPHA_THRESHOLD
+}
+
+fn default_lock_feedback_log() -> bool {
+    true
+}
+
+#[allow(clippy::unnecessary_wraps)] // serde defaults must match the field type
+fn default_near_duplicate_distance() -> Option<u32> {
+    Some(4)
+}
+
+fn default_request_timeout_secs() -> u64 {
+    60
+}
+
+fn default_poll_interval_secs() -> u64 {
+    2
+}
+
+fn default_max_data_uri_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
+/// How many pixels the confidence heuristics sample per frame. Larger values cost
+/// more CPU but give more stable scores on large frames; frames smaller than the
+/// sample size are read in full.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfidenceConfig {
+    /// Pixels sampled when checking a frame isn't blank
+    pub validity_samples: usize,
+
+    /// Pixels sampled when comparing the keyframes to judge motion complexity
+    pub diff_samples: usize,
+
+    /// Pixels sampled for the brightness/saturation consistency check
+    pub stats_samples: usize,
+
+    /// Images whose brightness/saturation stats are kept for reuse, e.g. a keyframe
+    /// held across several pairs (0 disables the cache)
+    pub stats_cache_size: usize,
+
+    /// How color consistency is judged: "srgb" compares mean brightness and saturation,
+    /// "lab" the perceptual (CIEDE2000) difference between mean colors
+    pub color_space: ColorSpace,
+
+    /// Decide auto-accept/reject on a moving average of each frame's score and its
+    /// neighbours', so one noisy low score in a good sequence isn't flagged. Reported
+    /// scores stay unsmoothed.
+    pub smooth_scores: bool,
+
+    /// Frames averaged with `smooth_scores`, centered on the frame (odd, at least 3)
+    pub smoothing_window: usize,
+}
+
+/// Color space the color-consistency check compares frames in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    Lab,
+}
+
+impl Default for ConfidenceConfig {
+    fn default() -> Self {
+        Self {
+            validity_samples: 1000,
+            diff_samples: 500,
+            stats_samples: 500,
+            stats_cache_size: 16,
+            color_space: ColorSpace::default(),
+            smooth_scores: false,
+            smoothing_window: 3,
+        }
+    }
+}
+
+/// Keyframe pixel differences (0.0 - 1.0) separating the auto-detected motion types.
+/// A difference below `static_max` is "static", below `subtle_max` "subtle", below
+/// `normal_max` "normal", and anything larger "dynamic".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MotionThresholds {
+    pub static_max: f32,
+    pub subtle_max: f32,
+    pub normal_max: f32,
+}
+
+impl Default for MotionThresholds {
+    fn default() -> Self {
+        Self {
+            static_max: 0.05,
+            subtle_max: 0.15,
+            normal_max: 0.3,
+        }
+    }
+}
+
+/// Re-run a generation with a fresh seed when the whole sequence scores this low,
+/// keeping whichever attempt scores best
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryOnLowConfidence {
+    pub enabled: bool,
+
+    /// Retry while the sequence's mean frame confidence is below this (0.0 - 1.0)
+    pub mean_threshold: f32,
+
+    /// Most extra generations per request; each is billed like the first
+    pub max_retries: u32,
+}
+
+impl Default for RetryOnLowConfidence {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mean_threshold: 0.3,
+            max_retries: 1,
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             auto_accept_threshold: 0.85,
+            reject_threshold: None,
             feedback_log_path: None,
+            lock_feedback_log: true,
+            cache_dir: None,
+            near_duplicate_distance: default_near_duplicate_distance(),
+            repeated_frame_distance: None,
+            drop_repeated_frames: false,
+            upsample_when_short: false,
+            interpolate_alpha_only: false,
+            aspect_tolerance: None,
+            max_num_frames: default_max_num_frames(),
             api: ApiConfig {
                 backend: "replicate".to_string(),
                 endpoint: "http://localhost:8000/generate".to_string(),
                 api_key: None,
-                replicate_model: Some(
-                    "fofr/tooncrafter:0d5c6b3a4e0d6b8a9b8e7d6c5b4a3f2e1d0c9b8a".to_string(),
-                ),
+                api_key_file: None,
+                replicate_model: Some(format!(
+                    "fofr/tooncrafter:{}",
+                    crate::api::TOONCRAFTER_VERSION
+                )),
                 style_strength: 0.8,
-                timeout_secs: 180,
+                total_timeout_secs: 180,
+                request_timeout_secs: default_request_timeout_secs(),
+                poll_interval_secs: default_poll_interval_secs(),
+                max_data_uri_bytes: default_max_data_uri_bytes(),
+                native_frame_count: None,
+                extra_inputs: None,
+                proxy: None,
+                ca_bundle: None,
+                temp_dir: None,
+                native_frames_dir: None,
+                user_agent: None,
+                grayscale: false,
             },
             preprocessing: PreprocessingConfig {
                 cleanup_enabled: true,
                 target_resolution: 1024,
                 normalize_resolution: true,
                 min_stroke_length: 5.0,
+                binarize_alpha: true,
+                feather_radius: 0,
+                remove_background: None,
+                min_neighbors: default_min_neighbors(),
+                connectivity: Connectivity::Eight,
+                alpha_threshold: DEFAULT_ALPHA_THRESHOLD,
             },
+            confidence: ConfidenceConfig::default(),
+            motion_thresholds: MotionThresholds::default(),
+            retry_on_low_confidence: RetryOnLowConfidence::default(),
         }
     }
 }
 
+/// Backends `api.backend` may name
+pub const BACKENDS: [&str; 4] = ["replicate", "local", "serverless", "crossfade"];
+
+/// Problems found by [`Config::validate`]
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    /// Settings that would make generation fail or misbehave
+    pub errors: Vec<String>,
+    /// Legal but suspicious settings
+    pub warnings: Vec<String>,
+}
+
+impl ConfigReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
 impl Config {
-    /// Load configuration from a TOML file
+    /// Check value ranges, the backend name and (for Replicate) that an API key can be
+    /// found, without touching the network
+    pub fn validate(&self) -> ConfigReport {
+        let mut report = ConfigReport::default();
+        let mut error = |message: String| report.errors.push(message);
+
+        for message in self.threshold_errors() {
+            error(message);
+        }
+        if !(0.0..=1.0).contains(&self.api.style_strength) {
+            error(format!(
+                "api.style_strength must be between 0.0 and 1.0, got {}",
+                self.api.style_strength
+            ));
+        }
+        if !BACKENDS.contains(&self.api.backend.as_str()) {
+            error(format!(
+                "api.backend '{}' is not one of {}",
+                self.api.backend,
+                BACKENDS.join(", ")
+            ));
+        }
+        for (name, value) in [
+            ("total_timeout_secs", self.api.total_timeout_secs),
+            ("request_timeout_secs", self.api.request_timeout_secs),
+            ("poll_interval_secs", self.api.poll_interval_secs),
+            ("max_data_uri_bytes", self.api.max_data_uri_bytes as u64),
+        ] {
+            if value == 0 {
+                error(format!("api.{name} must be at least 1"));
+            }
+        }
+        if self.max_num_frames == 0 {
+            error("max_num_frames must be at least 1".to_string());
+        }
+        if self.preprocessing.target_resolution == 0 {
+            error("preprocessing.target_resolution must be at least 1".to_string());
+        }
+        let preprocessing = &self.preprocessing;
+        if preprocessing.min_neighbors > preprocessing.connectivity.neighbors() {
+            error(format!(
+                "preprocessing.min_neighbors must be at most {} with {:?} connectivity \
+                 (cleanup would erase everything), got {}",
+                preprocessing.connectivity.neighbors(),
+                preprocessing.connectivity,
+                preprocessing.min_neighbors
+            ));
+        }
+        if self.preprocessing.min_stroke_length < 0.0 {
+            error(format!(
+                "preprocessing.min_stroke_length must not be negative, got {}",
+                self.preprocessing.min_stroke_length
+            ));
+        }
+        if self.preprocessing.alpha_threshold == 0 {
+            error("preprocessing.alpha_threshold must be at least 1".to_string());
+        }
+        let confidence = &self.confidence;
+        if confidence.smooth_scores
+            && (confidence.smoothing_window < 3 || confidence.smoothing_window % 2 == 0)
+        {
+            error(format!(
+                "confidence.smoothing_window must be an odd number of at least 3, got {}",
+                confidence.smoothing_window
+            ));
+        }
+        let motion = &self.motion_thresholds;
+        if !(motion.static_max <= motion.subtle_max && motion.subtle_max <= motion.normal_max) {
+            error(format!(
+                "motion_thresholds must be ascending (static_max <= subtle_max <= normal_max), \
+                 got {} / {} / {}",
+                motion.static_max, motion.subtle_max, motion.normal_max
+            ));
+        }
+        if self.api.backend == "replicate" && std::env::var("REPLICATE_API_KEY").is_err() {
+            match crate::api::configured_api_key(&self.api) {
+                Ok(Some(_)) => {}
+                Ok(None) => error(
+                    "api.api_key is not set and REPLICATE_API_KEY is not in the environment"
+                        .to_string(),
+                ),
+                Err(e) => error(format!("API key could not be read: {e}")),
+            }
+        }
+
+        if self.api.total_timeout_secs > 0 && self.api.total_timeout_secs < 30 {
+            report.warnings.push(format!(
+                "api.total_timeout_secs = {} is short; generations usually take 30-60 seconds",
+                self.api.total_timeout_secs
+            ));
+        }
+        if self.api.poll_interval_secs > self.api.total_timeout_secs {
+            report.warnings.push(format!(
+                "api.poll_interval_secs ({}) exceeds total_timeout_secs ({}), so Replicate is \
+                 polled at most once",
+                self.api.poll_interval_secs, self.api.total_timeout_secs
+            ));
+        }
+        if (0.0..0.5).contains(&self.auto_accept_threshold) {
+            report.warnings.push(format!(
+                "auto_accept_threshold = {} auto-accepts most frames without review",
+                self.auto_accept_threshold
+            ));
+        }
+
+        report
+    }
+
+    /// Problems with the thresholds frames are judged by
+    fn threshold_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if !(0.0..=1.0).contains(&self.auto_accept_threshold) {
+            errors.push(format!(
+                "auto_accept_threshold must be between 0.0 and 1.0, got {}",
+                self.auto_accept_threshold
+            ));
+        }
+        if let Some(reject) = self.reject_threshold {
+            if !(0.0..=1.0).contains(&reject) {
+                errors.push(format!(
+                    "reject_threshold must be between 0.0 and 1.0, got {reject}"
+                ));
+            } else if reject >= self.auto_accept_threshold {
+                errors.push(format!(
+                    "reject_threshold ({}) must be below auto_accept_threshold ({})",
+                    reject, self.auto_accept_threshold
+                ));
+            }
+        }
+        let retry_floor = self.retry_on_low_confidence.mean_threshold;
+        if !(0.0..=1.0).contains(&retry_floor) {
+            errors.push(format!(
+                "retry_on_low_confidence.mean_threshold must be between 0.0 and 1.0, got \
+                 {retry_floor}"
+            ));
+        }
+        if let Some(tolerance) = self.aspect_tolerance.filter(|t| *t < 0.0) {
+            errors.push(format!(
+                "aspect_tolerance must not be negative, got {tolerance}"
+            ));
+        }
+        errors
+    }
+
+    /// Load configuration from a TOML file, or from an `http(s)://` URL given as the
+    /// path (see [`Config::load_url`])
     pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        if let Some(url) = path.to_str().filter(|path| is_url(path)) {
+            return Self::load_url(url);
+        }
         let contents = std::fs::read_to_string(path)?;
         let config: Config = toml::from_str(&contents)?;
         Ok(config)
     }
 
+    /// Download and parse a TOML config, or JSON if the URL ends in `.json` or the server
+    /// says so. Each successful fetch is cached, readable only by the current user, in
+    /// their cache directory; if the server can't be reached the cached copy is used with
+    /// a warning. The fetch goes through the proxy and timeout of the cached copy, or the
+    /// defaults before the first fetch.
+    pub fn load_url(url: &str) -> Result<Self, ConfigError> {
+        let cache_path = url_cache_path(url);
+        let cached = cache_path
+            .as_deref()
+            .filter(|path| path.is_file())
+            .and_then(|path| Self::load(path).ok());
+        let bootstrap = cached
+            .as_ref()
+            .map_or_else(|| Config::default().api, |config| config.api.clone());
+        let config = match (fetch_config(url, &bootstrap), cached) {
+            (Ok(config), _) => config,
+            (Err(e @ ConfigError::FetchError { .. }), Some(cached)) => {
+                log::warn!(
+                    "{}; FALLING BACK to the copy cached at {}, which may be out of date",
+                    e,
+                    cache_path.as_deref().unwrap_or(Path::new("")).display()
+                );
+                return Ok(cached);
+            }
+            (Err(e), _) => return Err(e),
+        };
+
+        if let Some(cache_path) = &cache_path {
+            let cached = config
+                .to_toml()
+                .and_then(|toml| Ok(write_private(cache_path, &toml)?));
+            if let Err(e) = cached {
+                log::warn!("Could not cache config from {}: {}", url, e);
+            }
+        }
+        Ok(config)
+    }
+
     /// Save configuration to a TOML file
     pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
-        let toml = toml::to_string_pretty(self)?;
-        std::fs::write(path, toml)?;
+        std::fs::write(path, self.to_toml()?)?;
         Ok(())
     }
 
-    /// Get the default config path (~/.config/gp_ai_inbetween/config.toml)
+    /// Render as the TOML `save` writes
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// A copy that is safe to paste into a bug report: a literal `api_key` and any proxy
+    /// password are masked. `file:`/`env:` key references hold no secret and are kept.
+    #[must_use]
+    pub fn redacted(&self) -> Self {
+        const MASK: &str = "<redacted>";
+        let mut config = self.clone();
+        if let Some(key) = &mut config.api.api_key {
+            if !key.starts_with("file:") && !key.starts_with("env:") {
+                *key = MASK.to_string();
+            }
+        }
+        if let Some(proxy) = &mut config.api.proxy {
+            let scheme_end = proxy.find("://").map_or(0, |i| i + 3);
+            // Only `user:password@` before the host holds a secret
+            if let Some(host_start) = proxy.rfind('@').filter(|&at| at >= scheme_end) {
+                if let Some(colon) = proxy[scheme_end..host_start].find(':') {
+                    proxy.replace_range(scheme_end + colon + 1..host_start, MASK);
+                }
+            }
+        }
+        config
+    }
+
+    /// Get the default config path (`~/.config/gp_ai_inbetween/config.toml`)
     pub fn default_path() -> Option<std::path::PathBuf> {
         dirs::config_dir().map(|p| p.join("gp_ai_inbetween").join("config.toml"))
     }
@@ -116,6 +833,286 @@ impl Config {
             .and_then(|p| Self::load(&p).ok())
             .unwrap_or_default()
     }
+
+    /// Start building a config from the defaults
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+/// Fluent construction of a [`Config`], starting from [`Config::default()`]
+///
+/// ```
+/// use gp_core::Config;
+///
+/// let config = Config::builder()
+///     .backend("local")
+///     .endpoint("http://localhost:9000/generate")
+///     .auto_accept_threshold(0.9)
+///     .target_resolution(512)
+///     .build();
+///
+/// assert_eq!(config.api.backend, "local");
+/// assert_eq!(config.preprocessing.target_resolution, 512);
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    pub fn auto_accept_threshold(mut self, threshold: f32) -> Self {
+        self.config.auto_accept_threshold = threshold;
+        self
+    }
+
+    pub fn reject_threshold(mut self, threshold: Option<f32>) -> Self {
+        self.config.reject_threshold = threshold;
+        self
+    }
+
+    pub fn feedback_log_path(mut self, path: impl Into<String>) -> Self {
+        self.config.feedback_log_path = Some(path.into());
+        self
+    }
+
+    pub fn lock_feedback_log(mut self, lock: bool) -> Self {
+        self.config.lock_feedback_log = lock;
+        self
+    }
+
+    pub fn cache_dir(mut self, dir: impl Into<String>) -> Self {
+        self.config.cache_dir = Some(dir.into());
+        self
+    }
+
+    pub fn near_duplicate_distance(mut self, distance: Option<u32>) -> Self {
+        self.config.near_duplicate_distance = distance;
+        self
+    }
+
+    pub fn repeated_frame_distance(mut self, distance: Option<u32>) -> Self {
+        self.config.repeated_frame_distance = distance;
+        self
+    }
+
+    pub fn drop_repeated_frames(mut self, drop: bool) -> Self {
+        self.config.drop_repeated_frames = drop;
+        self
+    }
+
+    pub fn upsample_when_short(mut self, upsample: bool) -> Self {
+        self.config.upsample_when_short = upsample;
+        self
+    }
+
+    pub fn interpolate_alpha_only(mut self, alpha_only: bool) -> Self {
+        self.config.interpolate_alpha_only = alpha_only;
+        self
+    }
+
+    pub fn aspect_tolerance(mut self, tolerance: Option<f32>) -> Self {
+        self.config.aspect_tolerance = tolerance;
+        self
+    }
+
+    pub fn max_num_frames(mut self, max: u32) -> Self {
+        self.config.max_num_frames = max;
+        self
+    }
+
+    pub fn backend(mut self, backend: impl Into<String>) -> Self {
+        self.config.api.backend = backend.into();
+        self
+    }
+
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.config.api.endpoint = endpoint.into();
+        self
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.config.api.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn api_key_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.api.api_key_file = Some(path.into());
+        self
+    }
+
+    pub fn replicate_model(mut self, model: impl Into<String>) -> Self {
+        self.config.api.replicate_model = Some(model.into());
+        self
+    }
+
+    pub fn style_strength(mut self, strength: f32) -> Self {
+        self.config.api.style_strength = strength;
+        self
+    }
+
+    #[deprecated(note = "use total_timeout_secs")]
+    pub fn timeout_secs(self, secs: u64) -> Self {
+        self.total_timeout_secs(secs)
+    }
+
+    pub fn total_timeout_secs(mut self, secs: u64) -> Self {
+        self.config.api.total_timeout_secs = secs;
+        self
+    }
+
+    pub fn request_timeout_secs(mut self, secs: u64) -> Self {
+        self.config.api.request_timeout_secs = secs;
+        self
+    }
+
+    pub fn poll_interval_secs(mut self, secs: u64) -> Self {
+        self.config.api.poll_interval_secs = secs;
+        self
+    }
+
+    pub fn native_frame_count(mut self, count: u32) -> Self {
+        self.config.api.native_frame_count = Some(count);
+        self
+    }
+
+    pub fn extra_inputs(mut self, inputs: serde_json::Value) -> Self {
+        self.config.api.extra_inputs = Some(inputs);
+        self
+    }
+
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.config.api.proxy = Some(proxy.into());
+        self
+    }
+
+    pub fn ca_bundle(mut self, path: impl Into<String>) -> Self {
+        self.config.api.ca_bundle = Some(path.into());
+        self
+    }
+
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.api.temp_dir = Some(dir.into());
+        self
+    }
+
+    pub fn native_frames_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.api.native_frames_dir = Some(dir.into());
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.api.user_agent = Some(user_agent.into());
+        self
+    }
+
+    pub fn max_data_uri_bytes(mut self, bytes: usize) -> Self {
+        self.config.api.max_data_uri_bytes = bytes;
+        self
+    }
+
+    pub fn grayscale(mut self, grayscale: bool) -> Self {
+        self.config.api.grayscale = grayscale;
+        self
+    }
+
+    pub fn cleanup_enabled(mut self, enabled: bool) -> Self {
+        self.config.preprocessing.cleanup_enabled = enabled;
+        self
+    }
+
+    pub fn target_resolution(mut self, resolution: u32) -> Self {
+        self.config.preprocessing.target_resolution = resolution;
+        self
+    }
+
+    pub fn normalize_resolution(mut self, normalize: bool) -> Self {
+        self.config.preprocessing.normalize_resolution = normalize;
+        self
+    }
+
+    pub fn min_stroke_length(mut self, length: f32) -> Self {
+        self.config.preprocessing.min_stroke_length = length;
+        self
+    }
+
+    pub fn binarize_alpha(mut self, binarize: bool) -> Self {
+        self.config.preprocessing.binarize_alpha = binarize;
+        self
+    }
+
+    pub fn alpha_threshold(mut self, threshold: u8) -> Self {
+        self.config.preprocessing.alpha_threshold = threshold;
+        self
+    }
+
+    pub fn feather_radius(mut self, radius: u32) -> Self {
+        self.config.preprocessing.feather_radius = radius;
+        self
+    }
+
+    pub fn remove_background(mut self, key: Option<BackgroundKey>) -> Self {
+        self.config.preprocessing.remove_background = key;
+        self
+    }
+
+    pub fn min_neighbors(mut self, min_neighbors: u32) -> Self {
+        self.config.preprocessing.min_neighbors = min_neighbors;
+        self
+    }
+
+    pub fn connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.config.preprocessing.connectivity = connectivity;
+        self
+    }
+
+    pub fn validity_samples(mut self, samples: usize) -> Self {
+        self.config.confidence.validity_samples = samples;
+        self
+    }
+
+    pub fn diff_samples(mut self, samples: usize) -> Self {
+        self.config.confidence.diff_samples = samples;
+        self
+    }
+
+    pub fn stats_samples(mut self, samples: usize) -> Self {
+        self.config.confidence.stats_samples = samples;
+        self
+    }
+
+    pub fn stats_cache_size(mut self, size: usize) -> Self {
+        self.config.confidence.stats_cache_size = size;
+        self
+    }
+
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.config.confidence.color_space = color_space;
+        self
+    }
+
+    pub fn smooth_scores(mut self, window: Option<usize>) -> Self {
+        self.config.confidence.smooth_scores = window.is_some();
+        if let Some(window) = window {
+            self.config.confidence.smoothing_window = window;
+        }
+        self
+    }
+
+    pub fn motion_thresholds(mut self, thresholds: MotionThresholds) -> Self {
+        self.config.motion_thresholds = thresholds;
+        self
+    }
+
+    pub fn retry_on_low_confidence(mut self, retry: RetryOnLowConfidence) -> Self {
+        self.config.retry_on_low_confidence = retry;
+        self
+    }
+
+    pub fn build(self) -> Config {
+        self.config
+    }
 }
 
 #[cfg(test)]
@@ -137,4 +1134,112 @@ mod tests {
         let parsed: Config = toml::from_str(&toml).unwrap();
         assert_eq!(parsed.api.backend, config.api.backend);
     }
+
+    #[test]
+    fn test_near_duplicate_check_on_in_older_configs() {
+        let toml = toml::to_string(&Config::default())
+            .unwrap()
+            .replace("near_duplicate_distance = 4\n", "");
+        assert!(!toml.contains("near_duplicate_distance"));
+        let parsed: Config = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.near_duplicate_distance, Some(4));
+    }
+
+    #[test]
+    fn test_extra_inputs_from_toml() {
+        let mut config = Config::default();
+        config.api.extra_inputs = Some(serde_json::json!({ "fps": 8, "prompt": "ink" }));
+
+        let toml = toml::to_string(&config).unwrap();
+        let parsed: Config = toml::from_str(&toml).unwrap();
+        let extra = parsed.api.extra_inputs.unwrap();
+        assert_eq!(extra["fps"], 8);
+        assert_eq!(extra["prompt"], "ink");
+    }
+
+    #[test]
+    fn test_timeout_secs_alias() {
+        let config = Config::default();
+        let toml = toml::to_string(&config)
+            .unwrap()
+            .replace("total_timeout_secs = 180", "timeout_secs = 90")
+            .replace("request_timeout_secs = 60\n", "")
+            .replace("poll_interval_secs = 2\n", "");
+
+        let parsed: Config = toml::from_str(&toml).unwrap();
+        assert_eq!(parsed.api.total_timeout_secs, 90);
+        assert_eq!(parsed.api.request_timeout_secs, 60);
+        assert_eq!(parsed.api.poll_interval_secs, 2);
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = Config::builder()
+            .backend("crossfade")
+            .total_timeout_secs(5)
+            .build();
+        let report = config.validate();
+        assert!(report.is_valid(), "{:?}", report.errors);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].contains("total_timeout_secs = 5"));
+
+        let mut config = Config::builder()
+            .backend("cloud")
+            .auto_accept_threshold(1.5)
+            .build();
+        config.motion_thresholds.static_max = 0.5;
+        let report = config.validate();
+        assert_eq!(report.errors.len(), 3, "{:?}", report.errors);
+        assert!(report.errors[1].contains("'cloud'"));
+
+        let config = Config::builder()
+            .backend("crossfade")
+            .reject_threshold(Some(0.9))
+            .build();
+        let report = config.validate();
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert!(report.errors[0].contains("below auto_accept_threshold"));
+    }
+
+    #[test]
+    fn test_load_from_url() {
+        use crate::test_util::{http_response, serve};
+
+        let toml = Config::builder()
+            .backend("crossfade")
+            .target_resolution(256)
+            .build()
+            .to_toml()
+            .unwrap();
+        let json = serde_json::to_string(&Config::builder().backend("local").build()).unwrap();
+        let base = serve(vec![
+            http_response("200 OK", "text/plain", toml.as_bytes()),
+            http_response("200 OK", "application/json", json.as_bytes()),
+        ]);
+        let url = format!("{base}/farm/config.toml");
+
+        // A URL works anywhere a config path does
+        let config = Config::load(Path::new(&url)).unwrap();
+        assert_eq!(config.api.backend, "crossfade");
+        assert_eq!(config.preprocessing.target_resolution, 256);
+
+        let config = Config::load_url(&url).unwrap();
+        assert_eq!(config.api.backend, "local");
+
+        // The server is gone now; the last fetch is used instead
+        let config = Config::load_url(&url).unwrap();
+        assert_eq!(config.api.backend, "local");
+        let cache_path = url_cache_path(&url).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&cache_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(cache_path).unwrap();
+        assert!(matches!(
+            Config::load_url(&url),
+            Err(ConfigError::FetchError { .. })
+        ));
+    }
 }
@@ -0,0 +1,110 @@
+use image::{imageops::FilterType, DynamicImage, GenericImageView, Rgb, RgbImage, RgbaImage};
+
+/// Heatmap colors from no difference (blue) to maximal difference (red)
+const HEATMAP: [[u8; 3]; 5] = [
+    [0, 0, 255],
+    [0, 255, 255],
+    [0, 255, 0],
+    [255, 255, 0],
+    [255, 0, 0],
+];
+
+/// Naive interpolation `(1 - t) * A + t * B`, the frame a plain cross-fade would give
+/// at position `t`. B is resized to A's dimensions if they differ.
+pub fn blend(img_a: &DynamicImage, img_b: &DynamicImage, t: f32) -> DynamicImage {
+    let t = t.clamp(0.0, 1.0);
+    let rgba_a = img_a.to_rgba8();
+    let rgba_b = if img_b.dimensions() == img_a.dimensions() {
+        img_b.to_rgba8()
+    } else {
+        img_b
+            .resize_exact(img_a.width(), img_a.height(), FilterType::Triangle)
+            .to_rgba8()
+    };
+
+    let mut blended = RgbaImage::new(rgba_a.width(), rgba_a.height());
+    for ((out, a), b) in blended
+        .pixels_mut()
+        .zip(rgba_a.pixels())
+        .zip(rgba_b.pixels())
+    {
+        for channel in 0..4 {
+            let value = (1.0 - t) * f32::from(a[channel]) + t * f32::from(b[channel]);
+            out[channel] = value.round() as u8;
+        }
+    }
+
+    DynamicImage::ImageRgba8(blended)
+}
+
+/// Color each pixel by how far `generated` strays from `expected`: blue where they
+/// match, through green and yellow, to red for the largest channel difference.
+/// `expected` is resized to the generated frame's dimensions if they differ.
+pub fn diff_heatmap(generated: &DynamicImage, expected: &DynamicImage) -> DynamicImage {
+    let (width, height) = generated.dimensions();
+    let generated = generated.to_rgba8();
+    let expected = if expected.dimensions() == (width, height) {
+        expected.to_rgba8()
+    } else {
+        expected
+            .resize_exact(width, height, FilterType::Triangle)
+            .to_rgba8()
+    };
+
+    let mut heatmap = RgbImage::new(width, height);
+    for ((out, a), b) in heatmap
+        .pixels_mut()
+        .zip(generated.pixels())
+        .zip(expected.pixels())
+    {
+        let difference =
+            a.0.iter()
+                .zip(b.0.iter())
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or(0);
+        *out = heat_color(f32::from(difference) / 255.0);
+    }
+
+    DynamicImage::ImageRgb8(heatmap)
+}
+
+/// Map 0.0-1.0 onto the heatmap ramp
+pub(crate) fn heat_color(value: f32) -> Rgb<u8> {
+    let scaled = value.clamp(0.0, 1.0) * (HEATMAP.len() - 1) as f32;
+    let low = (scaled.floor() as usize).min(HEATMAP.len() - 2);
+    let fraction = scaled - low as f32;
+
+    let mix = |channel: usize| {
+        let from = f32::from(HEATMAP[low][channel]);
+        let to = f32::from(HEATMAP[low + 1][channel]);
+        (from + (to - from) * fraction).round() as u8
+    };
+    Rgb([mix(0), mix(1), mix(2)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_frames_are_cold() {
+        let frame = DynamicImage::ImageRgba8(RgbaImage::from_fn(16, 16, |x, y| {
+            image::Rgba([(x * 16) as u8, (y * 16) as u8, 40, 255])
+        }));
+
+        let heatmap = diff_heatmap(&frame, &blend(&frame, &frame, 0.3)).to_rgb8();
+        assert!(heatmap.pixels().all(|pixel| pixel.0 == HEATMAP[0]));
+
+        // A frame that is all A where the blend expects all B is hot everywhere
+        let black =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 0, 255])));
+        let white = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            4,
+            4,
+            image::Rgba([255, 255, 255, 255]),
+        ));
+        let heatmap = diff_heatmap(&black, &blend(&black, &white, 1.0)).to_rgb8();
+        assert_eq!(heatmap.get_pixel(0, 0).0, HEATMAP[4]);
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How inbetweens are spaced in time between the keyframes. Drives the position each
+/// frame is scored at (and so its blend reference) and the positions in metadata.json.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    /// Evenly spaced
+    #[default]
+    Linear,
+    /// Starts slowly: frames cluster toward keyframe A
+    EaseIn,
+    /// Ends slowly: frames cluster toward keyframe B
+    EaseOut,
+    /// Explicit position (0.0 = A, 1.0 = B) per frame
+    Custom(Vec<f32>),
+}
+
+impl Easing {
+    /// Position of inbetween `index` out of `count`, strictly between the keyframes for
+    /// the built-in curves. Custom lists too short for `index` fall back to linear.
+    pub fn position(&self, index: usize, count: usize) -> f32 {
+        let t = (index + 1) as f32 / (count + 1) as f32;
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::Custom(positions) => positions.get(index).copied().unwrap_or(t),
+        }
+    }
+
+    /// Positions of all `count` inbetweens, in order
+    pub fn positions(&self, count: usize) -> Vec<f32> {
+        (0..count).map(|i| self.position(i, count)).collect()
+    }
+}
+
+impl FromStr for Easing {
+    type Err = String;
+
+    /// `linear`, `ease-in`, `ease-out`, or comma-separated positions like `0.1,0.3,0.7`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "linear" => Ok(Self::Linear),
+            "ease-in" => Ok(Self::EaseIn),
+            "ease-out" => Ok(Self::EaseOut),
+            _ => {
+                let positions = value
+                    .split(',')
+                    .map(|part| {
+                        part.trim()
+                            .parse::<f32>()
+                            .ok()
+                            .filter(|p| (0.0..=1.0).contains(p))
+                            .ok_or_else(|| {
+                                format!(
+                                    "expected linear, ease-in, ease-out or positions between \
+                                     0.0 and 1.0 like 0.1,0.3,0.7, got '{value}'"
+                                )
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Self::Custom(positions))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ease_in_clusters_toward_a() {
+        let linear = Easing::Linear.positions(4);
+        let eased = Easing::EaseIn.positions(4);
+
+        assert!((linear[1] - 0.4).abs() < 1e-6);
+        assert!(eased.iter().zip(&linear).all(|(e, l)| e < l));
+        assert!(eased.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(Easing::EaseOut.positions(4)[0] > linear[0]);
+
+        assert_eq!(
+            "0.1, 0.5".parse::<Easing>().unwrap().positions(2),
+            vec![0.1, 0.5]
+        );
+        assert!("fast".parse::<Easing>().is_err());
+        assert!("0.2,1.5".parse::<Easing>().is_err());
+    }
+}
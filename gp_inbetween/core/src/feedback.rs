@@ -1,9 +1,13 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs::OpenOptions;
-use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use fs2::FileExt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FeedbackEntry {
@@ -13,8 +17,12 @@ pub struct FeedbackEntry {
     pub motion_type: String,
     pub frame_number: Option<u32>,
     pub auto_accepted: Option<bool>,
-    pub issues: Option<Vec<String>>,
+    pub issues: Option<Vec<IssueCategory>>,
     pub confidence_score: Option<f32>,
+    /// Frames in the generation an accepted or rejected frame came from, with
+    /// `frame_number` its 0-based index (absent in entries from older versions)
+    #[serde(default)]
+    pub total_frames: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -25,6 +33,75 @@ pub enum FeedbackEvent {
     Reject,
 }
 
+/// Why a frame was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IssueCategory {
+    Artifacts,
+    Ghosting,
+    WrongColor,
+    MissingLimb,
+    Flicker,
+    Other(String),
+}
+
+impl IssueCategory {
+    /// Parse user input, case-insensitively and tolerating common spelling variants.
+    /// Anything unrecognized is kept verbatim as `Other`.
+    pub fn parse(input: &str) -> Self {
+        let trimmed = input.trim();
+        let normalized = trimmed.to_lowercase().replace([' ', '-'], "_");
+
+        match normalized.as_str() {
+            "artifacts" | "artifact" | "artefacts" | "artefact" => Self::Artifacts,
+            "ghosting" | "ghost" | "ghosts" => Self::Ghosting,
+            "wrong_color" | "wrong_colour" | "wrong_colors" | "wrong_colours" => Self::WrongColor,
+            // "missing_parts" is what the Blender addon's reject dialog sends
+            "missing_limb" | "missing_limbs" | "missing_parts" => Self::MissingLimb,
+            "flicker" | "flickering" => Self::Flicker,
+            _ => Self::Other(trimmed.to_string()),
+        }
+    }
+
+    /// Canonical name used in the feedback log and statistics
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Artifacts => "artifacts",
+            Self::Ghosting => "ghosting",
+            Self::WrongColor => "wrong_color",
+            Self::MissingLimb => "missing_limb",
+            Self::Flicker => "flicker",
+            Self::Other(text) => text,
+        }
+    }
+}
+
+impl fmt::Display for IssueCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for IssueCategory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for IssueCategory {
+    /// Canonical names map to their category; older free-text entries become `Other`
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Ok(match text.as_str() {
+            "artifacts" => Self::Artifacts,
+            "ghosting" => Self::Ghosting,
+            "wrong_color" => Self::WrongColor,
+            "missing_limb" => Self::MissingLimb,
+            "flicker" => Self::Flicker,
+            _ => Self::Other(text),
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct Statistics {
     pub total_generations: u32,
@@ -32,33 +109,218 @@ pub struct Statistics {
     pub rejected: u32,
     pub acceptance_rate: f32,
     pub auto_accepted: u32,
-    pub by_motion_type: Vec<(String, f32)>,
-    pub by_character: Vec<(String, f32)>,
+    pub by_motion_type: Vec<GroupStats>,
+    pub by_character: Vec<GroupStats>,
+    /// Acceptance per band of position between the keyframes (e.g. "0.40-0.60"), from
+    /// the entries that recorded their generation's frame count
+    pub by_position: Vec<GroupStats>,
     pub common_issues: Vec<(String, u32)>,
 }
 
-pub struct FeedbackLogger {
-    log_path: PathBuf,
+/// What auto-accept would have done at a given threshold, replayed over the accept and
+/// reject events that recorded a confidence score
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ThresholdSimulation {
+    pub threshold: f32,
+    /// Scored accept/reject events replayed
+    pub frames: u32,
+    /// Frames scoring at or above the threshold
+    pub would_auto_accept: u32,
+    /// Frames that were auto-accepted at the time
+    pub auto_accepted: u32,
+    /// Would be auto-accepted, but were rejected
+    pub false_accepts: u32,
+    /// Would go to review, but were accepted
+    pub false_rejects: u32,
 }
 
-impl FeedbackLogger {
-    pub fn new() -> Result<Self> {
-        let log_path = Self::default_log_path()?;
+/// Width of the position bands in [`Statistics::by_position`]
+const POSITION_BAND: f32 = 0.2;
+
+/// Acceptance for one motion type or character, with the counts behind the rate so
+/// consumers can weight it by sample size
+#[derive(Debug, Serialize, Clone)]
+pub struct GroupStats {
+    pub name: String,
+    pub acceptance_rate: f32,
+    pub accepted: u32,
+    pub rejected: u32,
+}
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Time window used to group feedback in trend reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrendBucket {
+    Day,
+    Week,
+    Month,
+}
+
+impl TrendBucket {
+    /// Start of the bucket containing `timestamp` (Unix seconds, UTC)
+    pub fn bucket_start(self, timestamp: u64) -> u64 {
+        let day = timestamp / SECS_PER_DAY;
 
-        // Ensure directory exists
-        if let Some(parent) = log_path.parent() {
-            std::fs::create_dir_all(parent)
-                .context("Failed to create feedback log directory")?;
+        let start_day = match self {
+            Self::Day => day,
+            // 1970-01-01 was a Thursday; shift so weeks start on Monday
+            Self::Week => day.saturating_sub((day + 3) % 7),
+            Self::Month => {
+                let (year, month, _) = civil_from_days(day as i64);
+                days_from_civil(year, month, 1) as u64
+            }
+        };
+
+        start_day * SECS_PER_DAY
+    }
+}
+
+impl std::str::FromStr for TrendBucket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            other => Err(format!(
+                "unknown trend bucket '{other}' (expected day, week or month)"
+            )),
         }
+    }
+}
+
+/// Format a Unix timestamp as a UTC calendar date (YYYY-MM-DD)
+pub fn format_date(timestamp: u64) -> String {
+    let (year, month, day) = civil_from_days((timestamp / SECS_PER_DAY) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
 
-        Ok(Self { log_path })
+/// Convert days since the Unix epoch to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month as u32, day as u32)
+}
+
+/// Convert a civil date to days since the Unix epoch
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+pub struct FeedbackLogger {
+    storage: Storage,
+    /// Serializes appends from concurrent generations; reads never take it
+    append_lock: Mutex<()>,
+    /// Also lock the log file itself, against other processes
+    file_locking: bool,
+}
+
+enum Storage {
+    File(PathBuf),
+    /// Entries kept only for the life of the process
+    Memory(Mutex<Vec<FeedbackEntry>>),
+    /// Several logs read as one, e.g. collected from a team; read-only
+    Merged(Vec<PathBuf>),
+}
+
+impl FeedbackLogger {
+    /// Log to the default location, falling back to in-memory logging (with a warning)
+    /// if that location can't be written, e.g. a read-only home on a render farm
+    pub fn new() -> Result<Self> {
+        Ok(Self::open_or_in_memory(Self::default_log_path()?))
     }
 
     pub fn with_path(path: PathBuf) -> Result<Self> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        Ok(Self { log_path: path })
+        Ok(Self {
+            storage: Storage::File(path),
+            append_lock: Mutex::new(()),
+            file_locking: true,
+        })
+    }
+
+    /// Read several logs as one, for combined statistics. Entries are ordered by
+    /// timestamp, and an entry repeated across logs (the same log collected twice) is
+    /// counted once; repeats within one log are kept. Logging to a merged logger fails.
+    pub fn from_paths(paths: Vec<PathBuf>) -> Result<Self> {
+        if let Some(missing) = paths.iter().find(|path| !path.is_file()) {
+            anyhow::bail!("Feedback log not found: {}", missing.display());
+        }
+        Ok(Self {
+            storage: Storage::Merged(paths),
+            append_lock: Mutex::new(()),
+            file_locking: true,
+        })
+    }
+
+    /// A logger that keeps feedback in memory only; nothing is persisted
+    pub fn in_memory() -> Self {
+        Self {
+            storage: Storage::Memory(Mutex::new(Vec::new())),
+            append_lock: Mutex::new(()),
+            file_locking: true,
+        }
+    }
+
+    /// Whether to take advisory locks on the log file (on by default). Locks only
+    /// guard against other processes; appends within one are serialized regardless.
+    #[must_use]
+    pub fn with_file_locking(mut self, enabled: bool) -> Self {
+        self.file_locking = enabled;
+        self
+    }
+
+    /// Whether feedback is being written to disk
+    pub fn is_persistent(&self) -> bool {
+        matches!(self.storage, Storage::File(_))
+    }
+
+    fn open_or_in_memory(log_path: PathBuf) -> Self {
+        let writable = log_path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| OpenOptions::new().create(true).append(true).open(&log_path));
+
+        match writable {
+            Ok(_) => Self {
+                storage: Storage::File(log_path),
+                append_lock: Mutex::new(()),
+                file_locking: true,
+            },
+            Err(e) => {
+                log::warn!(
+                    "Cannot write feedback log {} ({}); feedback will not be saved",
+                    log_path.display(),
+                    e
+                );
+                Self::in_memory()
+            }
+        }
     }
 
     fn default_log_path() -> Result<PathBuf> {
@@ -72,23 +334,87 @@ impl FeedbackLogger {
     fn current_timestamp() -> u64 {
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0)
+            .map_or(0, |d| d.as_secs())
     }
 
     fn append_entry(&self, entry: &FeedbackEntry) -> Result<()> {
+        let log_path = match &self.storage {
+            Storage::File(path) => path,
+            Storage::Memory(entries) => {
+                entries
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .push(entry.clone());
+                return Ok(());
+            }
+            Storage::Merged(_) => anyhow::bail!("Merged feedback logs are read-only"),
+        };
+
+        // One write per line, so a concurrent reader never sees half an entry
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let _guard = self
+            .append_lock
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
-            .open(&self.log_path)
+            .open(log_path)
             .context("Failed to open feedback log")?;
+        self.lock_file(&file, log_path, true);
+        file.write_all(line.as_bytes())?;
 
-        let json = serde_json::to_string(entry)?;
-        writeln!(file, "{json}")?;
-
+        // Closing the file releases the lock
         Ok(())
     }
 
+    /// Drop a half-written last line left by a crash mid-append, so reads stop warning
+    /// about it and the next entry doesn't land on the end of it. A last line that is
+    /// a complete entry missing only its newline is kept and terminated instead.
+    /// Returns the number of bytes removed; logs not backed by a file are left alone.
+    pub fn repair(&self) -> Result<u64> {
+        let Storage::File(log_path) = &self.storage else {
+            return Ok(0);
+        };
+
+        let _guard = self
+            .append_lock
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut file = match OpenOptions::new().read(true).write(true).open(log_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).context("Failed to open feedback log"),
+        };
+        self.lock_file(&file, log_path, true);
+
+        let (line_start, tail) = unterminated_tail(&mut file)?;
+        if tail.is_empty() {
+            return Ok(0);
+        }
+        if serde_json::from_slice::<FeedbackEntry>(&tail).is_ok() {
+            file.seek(SeekFrom::End(0))?;
+            file.write_all(b"\n")?;
+            return Ok(0);
+        }
+
+        log::warn!(
+            "Removing a half-written line ({} bytes) from the end of {}",
+            tail.len(),
+            log_path.display()
+        );
+        file.set_len(line_start)?;
+        Ok(tail.len() as u64)
+    }
+
+    fn lock_file(&self, file: &File, path: &Path, exclusive: bool) {
+        if self.file_locking {
+            lock_or_warn(file, path, exclusive);
+        }
+    }
+
     /// Log a generation event
     pub fn log_generation(
         &self,
@@ -112,12 +438,14 @@ impl FeedbackLogger {
             auto_accepted: None,
             issues: None,
             confidence_score: None,
+            total_frames: None,
         };
 
         self.append_entry(&entry)
     }
 
-    /// Log frame acceptance
+    /// Log frame acceptance. With `total_frames`, `frame_number` is the frame's 0-based
+    /// index in its generation and counts toward the per-position statistics.
     pub fn log_acceptance(
         &self,
         frame_number: u32,
@@ -125,6 +453,7 @@ impl FeedbackLogger {
         motion_type: &str,
         auto_accepted: bool,
         confidence_score: Option<f32>,
+        total_frames: Option<u32>,
     ) -> Result<()> {
         log::info!(
             "Logging acceptance: frame={}, character={}, motion={}, auto={}",
@@ -143,19 +472,21 @@ impl FeedbackLogger {
             auto_accepted: Some(auto_accepted),
             issues: None,
             confidence_score,
+            total_frames,
         };
 
         self.append_entry(&entry)
     }
 
-    /// Log frame rejection
+    /// Log frame rejection; `total_frames` as for [`Self::log_acceptance`]
     pub fn log_rejection(
         &self,
         frame_number: u32,
         character: &str,
         motion_type: &str,
-        issues: &[String],
+        issues: &[IssueCategory],
         confidence_score: Option<f32>,
+        total_frames: Option<u32>,
     ) -> Result<()> {
         log::info!(
             "Logging rejection: frame={}, character={}, motion={}, issues={:?}",
@@ -174,34 +505,36 @@ impl FeedbackLogger {
             auto_accepted: None,
             issues: Some(issues.to_vec()),
             confidence_score,
+            total_frames,
         };
 
         self.append_entry(&entry)
     }
 
-    /// Read all entries from the log
-    fn read_entries(&self) -> Result<Vec<FeedbackEntry>> {
-        if !self.log_path.exists() {
-            return Ok(Vec::new());
-        }
-
-        let file = std::fs::File::open(&self.log_path)?;
-        let reader = BufReader::new(file);
-
-        let mut entries = Vec::new();
-        for line in reader.lines() {
-            let line = line?;
-            if line.trim().is_empty() {
-                continue;
-            }
-            if let Ok(entry) = serde_json::from_str::<FeedbackEntry>(&line) {
-                entries.push(entry);
-            } else {
-                log::warn!("Failed to parse feedback entry: {}", line);
-            }
-        }
+    /// Stream entries from the log without loading it all into memory. A file log is
+    /// read up to its length when iteration starts, without holding its lock, so the
+    /// caller may log more entries while iterating.
+    ///
+    /// Blank lines are skipped, and malformed ones are skipped with a warning; read
+    /// errors are yielded so the caller can stop.
+    pub fn iter_entries(&self) -> impl Iterator<Item = Result<FeedbackEntry>> {
+        let entries: Box<dyn Iterator<Item = Result<FeedbackEntry>>> = match &self.storage {
+            Storage::Memory(entries) => Box::new(
+                entries
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .clone()
+                    .into_iter()
+                    .map(Ok),
+            ),
+            Storage::File(log_path) => file_entries(log_path, self.file_locking),
+            Storage::Merged(paths) => match merge_logs(paths, self.file_locking) {
+                Ok(merged) => Box::new(merged.into_iter().map(Ok)),
+                Err(e) => Box::new(std::iter::once(Err(e))),
+            },
+        };
 
-        Ok(entries)
+        entries
     }
 
     /// Get acceptance rate filtered by character and/or motion type
@@ -210,12 +543,12 @@ impl FeedbackLogger {
         character: Option<&str>,
         motion_type: Option<&str>,
     ) -> Result<f32> {
-        let entries = self.read_entries()?;
-
         let mut accepts = 0u32;
         let mut rejects = 0u32;
 
-        for entry in entries {
+        for entry in self.iter_entries() {
+            let entry = entry?;
+
             // Filter by character if specified
             if let Some(ch) = character {
                 if entry.character != ch {
@@ -245,135 +578,436 @@ impl FeedbackLogger {
         Ok(accepts as f32 / total as f32)
     }
 
+    /// Acceptance rate per time bucket, oldest first.
+    ///
+    /// `since` is inclusive and `until` exclusive (Unix seconds). Buckets with no
+    /// accept/reject events are omitted rather than reported as 0%.
+    pub fn acceptance_trend(
+        &self,
+        bucket: TrendBucket,
+        since: Option<u64>,
+        until: Option<u64>,
+    ) -> Result<Vec<(u64, f32)>> {
+        let mut buckets: BTreeMap<u64, (u32, u32)> = BTreeMap::new();
+
+        for entry in self.iter_entries() {
+            let entry = entry?;
+            if since.is_some_and(|s| entry.timestamp < s)
+                || until.is_some_and(|u| entry.timestamp >= u)
+            {
+                continue;
+            }
+
+            let start = bucket.bucket_start(entry.timestamp);
+            match entry.event {
+                FeedbackEvent::Accept => buckets.entry(start).or_insert((0, 0)).0 += 1,
+                FeedbackEvent::Reject => buckets.entry(start).or_insert((0, 0)).1 += 1,
+                FeedbackEvent::Generation => {}
+            }
+        }
+
+        Ok(buckets
+            .into_iter()
+            .map(|(start, (acc, rej))| (start, acc as f32 / (acc + rej) as f32))
+            .collect())
+    }
+
     /// Get comprehensive statistics
     pub fn get_stats(
         &self,
         character: Option<&str>,
         motion_type: Option<&str>,
     ) -> Result<Statistics> {
-        let entries = self.read_entries()?;
+        compute_stats(self.iter_entries(), character, motion_type)
+    }
 
-        let mut total_generations = 0u32;
-        let mut accepted = 0u32;
-        let mut rejected = 0u32;
-        let mut auto_accepted = 0u32;
-        let mut by_motion_type: HashMap<String, (u32, u32)> = HashMap::new();
-        let mut by_character: HashMap<String, (u32, u32)> = HashMap::new();
-        let mut issue_counts: HashMap<String, u32> = HashMap::new();
+    /// Statistics over only the most recent `last_n` accept/reject events, by timestamp.
+    ///
+    /// Unlike a time window this counts events, so it stays meaningful right after a
+    /// burst of work. Generation events logged within the window are included.
+    pub fn recent_stats(&self, last_n: usize) -> Result<Statistics> {
+        let mut entries = self.iter_entries().collect::<Result<Vec<_>>>()?;
+        // Stable, so events sharing a timestamp keep their log order
+        entries.sort_by_key(|entry| entry.timestamp);
 
-        for entry in entries {
-            // Filter by character if specified
-            if let Some(ch) = character {
-                if entry.character != ch {
-                    continue;
-                }
+        let feedback: Vec<usize> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !matches!(entry.event, FeedbackEvent::Generation))
+            .map(|(i, _)| i)
+            .collect();
+        let start = match last_n {
+            0 => entries.len(),
+            n => feedback.len().checked_sub(n).map_or(0, |i| feedback[i]),
+        };
+
+        compute_stats(entries.into_iter().skip(start).map(Ok), None, None)
+    }
+
+    /// Replay the logged outcomes against a different `auto_accept_threshold`, e.g. to
+    /// see how many rejected frames a lower threshold would have let through. Frames
+    /// that were auto-accepted count as accepted.
+    pub fn simulate_threshold(&self, threshold: f32) -> Result<ThresholdSimulation> {
+        let mut simulation = ThresholdSimulation {
+            threshold,
+            frames: 0,
+            would_auto_accept: 0,
+            auto_accepted: 0,
+            false_accepts: 0,
+            false_rejects: 0,
+        };
+
+        for entry in self.iter_entries() {
+            let entry = entry?;
+            let Some(score) = entry.confidence_score else {
+                continue;
+            };
+            let accepted = match entry.event {
+                FeedbackEvent::Accept => true,
+                FeedbackEvent::Reject => false,
+                FeedbackEvent::Generation => continue,
+            };
+
+            let would_accept = score >= threshold;
+            simulation.frames += 1;
+            simulation.would_auto_accept += u32::from(would_accept);
+            simulation.auto_accepted += u32::from(entry.auto_accepted == Some(true));
+            simulation.false_accepts += u32::from(would_accept && !accepted);
+            simulation.false_rejects += u32::from(!would_accept && accepted);
+        }
+
+        Ok(simulation)
+    }
+}
+
+/// Aggregate entries into [`Statistics`], keeping only those matching the filters
+fn compute_stats(
+    entries: impl Iterator<Item = Result<FeedbackEntry>>,
+    character: Option<&str>,
+    motion_type: Option<&str>,
+) -> Result<Statistics> {
+    let mut total_generations = 0u32;
+    let mut accepted = 0u32;
+    let mut rejected = 0u32;
+    let mut auto_accepted = 0u32;
+    let mut by_motion_type: HashMap<String, (u32, u32)> = HashMap::new();
+    let mut by_character: HashMap<String, (u32, u32)> = HashMap::new();
+    let mut by_position: HashMap<String, (u32, u32)> = HashMap::new();
+    let mut issue_counts: HashMap<IssueCategory, u32> = HashMap::new();
+
+    for entry in entries {
+        let entry = entry?;
+
+        // Filter by character if specified
+        if let Some(ch) = character {
+            if entry.character != ch {
+                continue;
             }
+        }
 
-            // Filter by motion type if specified
-            if let Some(mt) = motion_type {
-                if entry.motion_type != mt {
-                    continue;
-                }
+        // Filter by motion type if specified
+        if let Some(mt) = motion_type {
+            if entry.motion_type != mt {
+                continue;
             }
+        }
 
+        if let Some(band) = position_band(&entry) {
+            let counts = by_position.entry(band).or_insert((0, 0));
             match entry.event {
-                FeedbackEvent::Generation => {
-                    total_generations += 1;
+                FeedbackEvent::Accept => counts.0 += 1,
+                FeedbackEvent::Reject => counts.1 += 1,
+                FeedbackEvent::Generation => {}
+            }
+        }
+
+        match entry.event {
+            FeedbackEvent::Generation => {
+                total_generations += 1;
+            }
+            FeedbackEvent::Accept => {
+                accepted += 1;
+
+                if entry.auto_accepted == Some(true) {
+                    auto_accepted += 1;
                 }
-                FeedbackEvent::Accept => {
-                    accepted += 1;
 
-                    if entry.auto_accepted == Some(true) {
-                        auto_accepted += 1;
-                    }
+                by_motion_type
+                    .entry(entry.motion_type.clone())
+                    .or_insert((0, 0))
+                    .0 += 1;
+
+                by_character
+                    .entry(entry.character.clone())
+                    .or_insert((0, 0))
+                    .0 += 1;
+            }
+            FeedbackEvent::Reject => {
+                rejected += 1;
 
-                    by_motion_type
-                        .entry(entry.motion_type.clone())
-                        .or_insert((0, 0))
-                        .0 += 1;
+                by_motion_type
+                    .entry(entry.motion_type.clone())
+                    .or_insert((0, 0))
+                    .1 += 1;
 
-                    by_character
-                        .entry(entry.character.clone())
-                        .or_insert((0, 0))
-                        .0 += 1;
-                }
-                FeedbackEvent::Reject => {
-                    rejected += 1;
-
-                    by_motion_type
-                        .entry(entry.motion_type.clone())
-                        .or_insert((0, 0))
-                        .1 += 1;
-
-                    by_character
-                        .entry(entry.character.clone())
-                        .or_insert((0, 0))
-                        .1 += 1;
-
-                    // Count issues
-                    if let Some(issues) = &entry.issues {
-                        for issue in issues {
-                            *issue_counts.entry(issue.clone()).or_insert(0) += 1;
-                        }
+                by_character
+                    .entry(entry.character.clone())
+                    .or_insert((0, 0))
+                    .1 += 1;
+
+                // Count issues
+                if let Some(issues) = &entry.issues {
+                    for issue in issues {
+                        *issue_counts.entry(issue.clone()).or_insert(0) += 1;
                     }
                 }
             }
         }
+    }
 
-        let acceptance_rate = if accepted + rejected > 0 {
-            accepted as f32 / (accepted + rejected) as f32
-        } else {
-            0.0
-        };
+    let acceptance_rate = if accepted + rejected > 0 {
+        accepted as f32 / (accepted + rejected) as f32
+    } else {
+        0.0
+    };
 
-        // Convert motion type stats to rates
-        let by_motion_type: Vec<(String, f32)> = by_motion_type
-            .into_iter()
-            .map(|(mt, (acc, rej))| {
-                let rate = if acc + rej > 0 {
-                    acc as f32 / (acc + rej) as f32
-                } else {
-                    0.0
-                };
-                (mt, rate)
-            })
-            .collect();
+    let by_motion_type = group_stats(by_motion_type);
+    let by_character = group_stats(by_character);
+    let by_position = group_stats(by_position);
 
-        // Convert character stats to rates
-        let by_character: Vec<(String, f32)> = by_character
-            .into_iter()
-            .map(|(ch, (acc, rej))| {
-                let rate = if acc + rej > 0 {
-                    acc as f32 / (acc + rej) as f32
-                } else {
-                    0.0
-                };
-                (ch, rate)
-            })
-            .collect();
+    // Sort issues by count
+    let mut common_issues: Vec<(String, u32)> = issue_counts
+        .into_iter()
+        .map(|(issue, count)| (issue.to_string(), count))
+        .collect();
+    common_issues.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
 
-        // Sort issues by count
-        let mut common_issues: Vec<(String, u32)> = issue_counts.into_iter().collect();
-        common_issues.sort_by(|a, b| b.1.cmp(&a.1));
-
-        Ok(Statistics {
-            total_generations,
-            accepted,
-            rejected,
-            acceptance_rate,
-            auto_accepted,
-            by_motion_type,
-            by_character,
-            common_issues,
+    Ok(Statistics {
+        total_generations,
+        accepted,
+        rejected,
+        acceptance_rate,
+        auto_accepted,
+        by_motion_type,
+        by_character,
+        by_position,
+        common_issues,
+    })
+}
+
+/// The position band an accept or reject entry falls in, e.g. "0.40-0.60". Frame `i` of
+/// `n` sits at `(i + 1) / (n + 1)` between the keyframes.
+fn position_band(entry: &FeedbackEntry) -> Option<String> {
+    let (index, total) = (entry.frame_number?, entry.total_frames?);
+    if index >= total {
+        return None;
+    }
+    let position = (index + 1) as f32 / (total + 1) as f32;
+    let bands = (1.0 / POSITION_BAND).round() as u32;
+    let band = ((position / POSITION_BAND) as u32).min(bands - 1);
+    let start = band as f32 * POSITION_BAND;
+    Some(format!("{:.2}-{:.2}", start, start + POSITION_BAND))
+}
+
+/// Convert per-group (accepted, rejected) counts to rates, sorted by name
+fn group_stats(counts: HashMap<String, (u32, u32)>) -> Vec<GroupStats> {
+    let mut groups: Vec<GroupStats> = counts
+        .into_iter()
+        .map(|(name, (accepted, rejected))| {
+            let acceptance_rate = if accepted + rejected > 0 {
+                accepted as f32 / (accepted + rejected) as f32
+            } else {
+                0.0
+            };
+            GroupStats {
+                name,
+                acceptance_rate,
+                accepted,
+                rejected,
+            }
         })
+        .collect();
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    groups
+}
+
+/// Stream one log file's entries (none if it doesn't exist yet). The file's length is
+/// taken under a shared lock if `locking`, so no append is half written, and the lock
+/// is released before streaming up to that length: `flock` locks belong to the open
+/// file, so holding it while the caller appends through another handle would deadlock.
+/// Entries are only ever appended, so the bytes before that length don't change
+/// (`repair` only drops a torn tail, which no locked append leaves behind).
+fn file_entries(log_path: &Path, locking: bool) -> Box<dyn Iterator<Item = Result<FeedbackEntry>>> {
+    match std::fs::File::open(log_path) {
+        Ok(file) => {
+            if locking {
+                lock_or_warn(&file, log_path, false);
+            }
+            let len = file.metadata().map(|metadata| metadata.len());
+            if locking {
+                let _ = FileExt::unlock(&file);
+            }
+            match len {
+                Ok(len) => Box::new(
+                    BufReader::new(file.take(len))
+                        .lines()
+                        .filter_map(parse_line),
+                ),
+                Err(e) => Box::new(std::iter::once(
+                    Err(e).context("Failed to read feedback log"),
+                )),
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Box::new(std::iter::empty()),
+        Err(e) => Box::new(std::iter::once(
+            Err(e).context("Failed to open feedback log"),
+        )),
+    }
+}
+
+/// Set once a lock has failed, so a log on a filesystem without locking warns only once
+static LOCK_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Block until `file` is locked (exclusively for writers). Locking is advisory and NFS
+/// implementations are unreliable, so a failure is logged and the caller carries on
+/// unlocked rather than losing the entry.
+fn lock_or_warn(file: &File, path: &Path, exclusive: bool) {
+    let locked = if exclusive {
+        FileExt::lock_exclusive(file)
+    } else {
+        FileExt::lock_shared(file)
+    };
+    if let Err(e) = locked {
+        if !LOCK_FAILED.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "Could not lock feedback log {} ({}); concurrent writers may interleave. \
+                 Set lock_feedback_log = false to skip locking.",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Where the file's last line starts and its bytes, if that line has no newline
+/// (empty if the file ends with one). Reads backwards, so a long log isn't scanned.
+fn unterminated_tail(file: &mut File) -> Result<(u64, Vec<u8>)> {
+    const CHUNK: u64 = 4096;
+    let len = file.seek(SeekFrom::End(0))?;
+    let mut tail = Vec::new();
+    let mut start = len;
+    while start > 0 {
+        let read_from = start.saturating_sub(CHUNK);
+        let mut chunk = vec![0; (start - read_from) as usize];
+        file.seek(SeekFrom::Start(read_from))?;
+        file.read_exact(&mut chunk)?;
+
+        if let Some(newline) = chunk.iter().rposition(|&b| b == b'\n') {
+            tail.splice(0..0, chunk.drain(newline + 1..));
+            return Ok((read_from + newline as u64 + 1, tail));
+        }
+        tail.splice(0..0, chunk);
+        start = read_from;
+    }
+    Ok((0, tail))
+}
+
+/// Every entry across `paths`, ordered by timestamp. Entries carry no id, so one that
+/// appears n times in a single log is kept n times, and other logs only add copies
+/// beyond the most any earlier log had: an identical entry logged twice in the same
+/// second is two events, but the same log collected twice is one.
+fn merge_logs(paths: &[PathBuf], locking: bool) -> Result<Vec<FeedbackEntry>> {
+    // Most copies of each serialized entry kept so far
+    let mut kept: HashMap<String, usize> = HashMap::new();
+    let mut merged = Vec::new();
+    for path in paths {
+        let mut in_file: HashMap<String, usize> = HashMap::new();
+        for entry in file_entries(path, locking) {
+            let entry = entry.with_context(|| format!("Failed to read {}", path.display()))?;
+            let key = serde_json::to_string(&entry)?;
+            let count = in_file.entry(key.clone()).or_default();
+            *count += 1;
+            let kept = kept.entry(key).or_default();
+            if *count > *kept {
+                *kept = *count;
+                merged.push(entry);
+            }
+        }
+    }
+
+    // Stable, so events sharing a timestamp keep their order
+    merged.sort_by_key(|entry| entry.timestamp);
+    Ok(merged)
+}
+
+/// Parse one log line, skipping blank and malformed ones
+fn parse_line(line: std::io::Result<String>) -> Option<Result<FeedbackEntry>> {
+    let line = match line {
+        Ok(line) => line,
+        Err(e) => return Some(Err(e.into())),
+    };
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    if let Ok(entry) = serde_json::from_str::<FeedbackEntry>(&line) {
+        Some(Ok(entry))
+    } else {
+        log::warn!("Failed to parse feedback entry: {}", line);
+        None
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{capture_warnings, warned};
     use tempfile::tempdir;
 
+    #[test]
+    fn test_unwritable_log_falls_back_to_memory() {
+        capture_warnings();
+
+        // A file where the log directory should be makes it impossible to create
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("blocked"), b"").unwrap();
+        let logger =
+            FeedbackLogger::open_or_in_memory(dir.path().join("blocked").join("feedback.jsonl"));
+
+        assert!(!logger.is_persistent());
+        assert!(warned("feedback will not be saved"));
+
+        logger.log_generation("hero", "walk", 4).unwrap();
+        logger
+            .log_acceptance(1, "hero", "walk", false, Some(0.9), None)
+            .unwrap();
+        let stats = logger.get_stats(None, None).unwrap();
+        assert_eq!(stats.total_generations, 1);
+        assert_eq!(stats.accepted, 1);
+    }
+
+    #[test]
+    fn test_iter_entries_skips_bad_lines() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("feedback.jsonl");
+        let logger = FeedbackLogger::with_path(log_path.clone()).unwrap();
+        assert_eq!(logger.iter_entries().count(), 0);
+
+        logger.log_generation("hero", "walk", 4).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&log_path).unwrap();
+        writeln!(file, "\n{{\"timestamp\": 1, \"event\"").unwrap();
+        drop(file);
+        logger
+            .log_acceptance(1, "hero", "walk", false, Some(0.9), None)
+            .unwrap();
+
+        let entries: Vec<_> = logger.iter_entries().collect::<Result<_>>().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[0].event, FeedbackEvent::Generation));
+        assert!(matches!(entries[1].event, FeedbackEvent::Accept));
+        assert_eq!(logger.get_stats(None, None).unwrap().accepted, 1);
+    }
+
     #[test]
     fn test_log_and_read() {
         let dir = tempdir().unwrap();
@@ -382,10 +1016,17 @@ mod tests {
 
         logger.log_generation("hero", "walk", 4).unwrap();
         logger
-            .log_acceptance(1, "hero", "walk", false, Some(0.9))
+            .log_acceptance(1, "hero", "walk", false, Some(0.9), None)
             .unwrap();
         logger
-            .log_rejection(2, "hero", "walk", &["artifacts".to_string()], Some(0.6))
+            .log_rejection(
+                2,
+                "hero",
+                "walk",
+                &[IssueCategory::Artifacts],
+                Some(0.6),
+                None,
+            )
             .unwrap();
 
         let stats = logger.get_stats(None, None).unwrap();
@@ -393,6 +1034,15 @@ mod tests {
         assert_eq!(stats.accepted, 1);
         assert_eq!(stats.rejected, 1);
         assert!((stats.acceptance_rate - 0.5).abs() < 0.01);
+
+        // Per-group breakdowns keep the counts behind each rate
+        assert_eq!(stats.by_character.len(), 1);
+        let hero = &stats.by_character[0];
+        assert_eq!(hero.name, "hero");
+        assert_eq!((hero.accepted, hero.rejected), (1, 1));
+        assert!((hero.acceptance_rate - 0.5).abs() < 0.01);
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["by_motion_type"][0]["rejected"], 1);
     }
 
     #[test]
@@ -402,13 +1052,13 @@ mod tests {
         let logger = FeedbackLogger::with_path(log_path).unwrap();
 
         logger
-            .log_acceptance(1, "hero", "walk", false, None)
+            .log_acceptance(1, "hero", "walk", false, None, None)
             .unwrap();
         logger
-            .log_acceptance(2, "hero", "walk", false, None)
+            .log_acceptance(2, "hero", "walk", false, None, None)
             .unwrap();
         logger
-            .log_rejection(3, "villain", "walk", &[], None)
+            .log_rejection(3, "villain", "walk", &[], None, None)
             .unwrap();
 
         let hero_rate = logger.get_acceptance_rate(Some("hero"), None).unwrap();
@@ -417,4 +1067,457 @@ mod tests {
         let villain_rate = logger.get_acceptance_rate(Some("villain"), None).unwrap();
         assert!((villain_rate - 0.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_issue_category_parsing() {
+        assert_eq!(IssueCategory::parse("Artifacts"), IssueCategory::Artifacts);
+        assert_eq!(IssueCategory::parse(" artefact "), IssueCategory::Artifacts);
+        assert_eq!(IssueCategory::parse("GHOSTING"), IssueCategory::Ghosting);
+        assert_eq!(
+            IssueCategory::parse("wrong colour"),
+            IssueCategory::WrongColor
+        );
+        assert_eq!(
+            IssueCategory::parse("missing-limb"),
+            IssueCategory::MissingLimb
+        );
+        assert_eq!(IssueCategory::parse("flickering"), IssueCategory::Flicker);
+        assert_eq!(
+            IssueCategory::parse("Blurry hands"),
+            IssueCategory::Other("Blurry hands".to_string())
+        );
+    }
+
+    #[test]
+    fn test_issues_aggregate_by_category() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("test_feedback.jsonl");
+
+        // Entry written before categories existed
+        std::fs::write(
+            &log_path,
+            r#"{"timestamp":1,"event":"reject","character":"hero","motion_type":"walk","frame_number":1,"auto_accepted":null,"issues":["artifacts","too blurry"],"confidence_score":null}"#
+                .to_string()
+                + "\n",
+        )
+        .unwrap();
+
+        let logger = FeedbackLogger::with_path(log_path).unwrap();
+        for raw in ["Artifact", "artefacts"] {
+            logger
+                .log_rejection(2, "hero", "walk", &[IssueCategory::parse(raw)], None, None)
+                .unwrap();
+        }
+
+        let stats = logger.get_stats(None, None).unwrap();
+        assert_eq!(stats.rejected, 3);
+        assert_eq!(stats.common_issues[0], ("artifacts".to_string(), 3));
+        assert!(stats.common_issues.contains(&("too blurry".to_string(), 1)));
+    }
+
+    #[test]
+    fn test_acceptance_trend_by_week() {
+        let dir = tempdir().unwrap();
+        let logger = FeedbackLogger::with_path(dir.path().join("test_feedback.jsonl")).unwrap();
+
+        // Monday 2024-01-01 00:00 UTC
+        let week_one = 1_704_067_200;
+        let week_two = week_one + 7 * SECS_PER_DAY;
+        let week_four = week_one + 21 * SECS_PER_DAY;
+
+        let events = [
+            (week_one, FeedbackEvent::Generation),
+            (week_one + 3600, FeedbackEvent::Reject),
+            (week_one + 2 * SECS_PER_DAY, FeedbackEvent::Accept),
+            (week_two + 60, FeedbackEvent::Accept),
+            (week_two + 6 * SECS_PER_DAY, FeedbackEvent::Accept),
+            (week_four, FeedbackEvent::Reject),
+        ];
+        for (timestamp, event) in events {
+            logger
+                .append_entry(&FeedbackEntry {
+                    timestamp,
+                    event,
+                    character: "hero".to_string(),
+                    motion_type: "walk".to_string(),
+                    frame_number: Some(1),
+                    auto_accepted: None,
+                    issues: None,
+                    confidence_score: None,
+                    total_frames: None,
+                })
+                .unwrap();
+        }
+
+        // Week three has no data and is skipped
+        let trend = logger
+            .acceptance_trend(TrendBucket::Week, None, None)
+            .unwrap();
+        assert_eq!(trend.len(), 3);
+        assert_eq!(trend[0].0, week_one);
+        assert!((trend[0].1 - 0.5).abs() < 0.01);
+        assert_eq!(trend[1].0, week_two);
+        assert!((trend[1].1 - 1.0).abs() < 0.01);
+        assert_eq!(trend[2].0, week_four);
+        assert!(trend[2].1.abs() < 0.01);
+
+        let bounded = logger
+            .acceptance_trend(TrendBucket::Week, Some(week_two), Some(week_four))
+            .unwrap();
+        assert_eq!(bounded.len(), 1);
+
+        let by_month = logger
+            .acceptance_trend(TrendBucket::Month, None, None)
+            .unwrap();
+        assert_eq!(by_month.len(), 1);
+        assert_eq!(format_date(by_month[0].0), "2024-01-01");
+    }
+
+    #[test]
+    fn test_trend_bucket_boundaries() {
+        // 2024-03-15 12:00 UTC (a Friday)
+        let timestamp = 1_710_504_000;
+        assert_eq!(
+            format_date(TrendBucket::Day.bucket_start(timestamp)),
+            "2024-03-15"
+        );
+        assert_eq!(
+            format_date(TrendBucket::Week.bucket_start(timestamp)),
+            "2024-03-11"
+        );
+        assert_eq!(
+            format_date(TrendBucket::Month.bucket_start(timestamp)),
+            "2024-03-01"
+        );
+    }
+
+    #[test]
+    fn test_recent_stats_counts_latest_events() {
+        let dir = tempdir().unwrap();
+        let logger = FeedbackLogger::with_path(dir.path().join("test_feedback.jsonl")).unwrap();
+
+        // Logged out of order: the three rejects are oldest
+        let events = [
+            (500, FeedbackEvent::Accept),
+            (100, FeedbackEvent::Reject),
+            (300, FeedbackEvent::Generation),
+            (400, FeedbackEvent::Accept),
+            (200, FeedbackEvent::Reject),
+            (150, FeedbackEvent::Reject),
+            (350, FeedbackEvent::Accept),
+        ];
+        for (timestamp, event) in events {
+            logger
+                .append_entry(&FeedbackEntry {
+                    timestamp,
+                    event,
+                    character: "hero".to_string(),
+                    motion_type: "walk".to_string(),
+                    frame_number: Some(1),
+                    auto_accepted: None,
+                    issues: None,
+                    confidence_score: None,
+                    total_frames: None,
+                })
+                .unwrap();
+        }
+
+        let recent = logger.recent_stats(3).unwrap();
+        assert_eq!(recent.accepted, 3);
+        assert_eq!(recent.rejected, 0);
+        assert!((recent.acceptance_rate - 1.0).abs() < 0.01);
+        // The generation at 300 precedes the window, which starts at 350
+        assert_eq!(recent.total_generations, 0);
+
+        let recent = logger.recent_stats(4).unwrap();
+        assert_eq!((recent.accepted, recent.rejected), (3, 1));
+        assert_eq!(recent.total_generations, 1);
+
+        // Asking for more than exist covers everything
+        let all = logger.recent_stats(100).unwrap();
+        assert_eq!((all.accepted, all.rejected), (3, 3));
+    }
+
+    #[test]
+    fn test_merged_logs_sum_totals() {
+        let dir = tempdir().unwrap();
+        let alice = FeedbackLogger::with_path(dir.path().join("alice.jsonl")).unwrap();
+        alice.log_generation("hero", "walk", 4).unwrap();
+        alice
+            .log_acceptance(1, "hero", "walk", false, None, None)
+            .unwrap();
+        alice
+            .log_rejection(2, "hero", "walk", &[IssueCategory::Ghosting], None, None)
+            .unwrap();
+        let bob = FeedbackLogger::with_path(dir.path().join("bob.jsonl")).unwrap();
+        bob.log_generation("villain", "run", 2).unwrap();
+        bob.log_acceptance(1, "villain", "run", true, None, None)
+            .unwrap();
+
+        // Collecting alice's log twice must not double count it
+        let merged = FeedbackLogger::from_paths(vec![
+            dir.path().join("alice.jsonl"),
+            dir.path().join("bob.jsonl"),
+            dir.path().join("alice.jsonl"),
+        ])
+        .unwrap();
+        let stats = merged.get_stats(None, None).unwrap();
+        assert_eq!(stats.total_generations, 2);
+        assert_eq!(stats.accepted, 2);
+        assert_eq!(stats.rejected, 1);
+        assert_eq!(stats.auto_accepted, 1);
+
+        let timestamps: Vec<_> = merged
+            .iter_entries()
+            .map(|e| e.unwrap().timestamp)
+            .collect();
+        assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        assert!(merged.log_generation("hero", "walk", 1).is_err());
+
+        // The same frame accepted twice within a second is two events, in each copy
+        let repeats = dir.path().join("repeats.jsonl");
+        let line = std::fs::read_to_string(dir.path().join("bob.jsonl")).unwrap();
+        let line = line.lines().last().unwrap();
+        std::fs::write(&repeats, format!("{line}\n{line}\n")).unwrap();
+        let merged = FeedbackLogger::from_paths(vec![
+            repeats.clone(),
+            dir.path().join("bob.jsonl"),
+            repeats,
+        ])
+        .unwrap();
+        assert_eq!(merged.get_stats(None, None).unwrap().accepted, 2);
+        assert!(FeedbackLogger::from_paths(vec![dir.path().join("carol.jsonl")]).is_err());
+    }
+
+    #[test]
+    fn test_simulate_threshold() {
+        let logger = FeedbackLogger::in_memory();
+        logger
+            .log_acceptance(0, "hero", "walk", true, Some(0.95), None)
+            .unwrap();
+        logger
+            .log_acceptance(1, "hero", "walk", false, Some(0.88), None)
+            .unwrap();
+        logger
+            .log_acceptance(2, "hero", "walk", false, Some(0.7), None)
+            .unwrap();
+        logger
+            .log_rejection(3, "hero", "walk", &[], Some(0.92), None)
+            .unwrap();
+        logger
+            .log_rejection(4, "hero", "walk", &[], Some(0.4), None)
+            .unwrap();
+        // Not replayable: no score, or not a review outcome
+        logger
+            .log_acceptance(5, "hero", "walk", false, None, None)
+            .unwrap();
+        logger.log_generation("hero", "walk", 4).unwrap();
+
+        let strict = logger.simulate_threshold(0.9).unwrap();
+        assert_eq!(
+            strict,
+            ThresholdSimulation {
+                threshold: 0.9,
+                frames: 5,
+                would_auto_accept: 2,
+                auto_accepted: 1,
+                false_accepts: 1,
+                false_rejects: 2,
+            }
+        );
+
+        let lenient = logger.simulate_threshold(0.6).unwrap();
+        assert_eq!(lenient.would_auto_accept, 4);
+        assert_eq!((lenient.false_accepts, lenient.false_rejects), (1, 0));
+    }
+
+    #[test]
+    fn test_repair_drops_truncated_last_line() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("feedback.jsonl");
+        let logger = FeedbackLogger::with_path(log_path.clone()).unwrap();
+        assert_eq!(logger.repair().unwrap(), 0);
+
+        logger.log_generation("hero", "walk", 4).unwrap();
+        logger
+            .log_acceptance(1, "hero", "walk", false, Some(0.9), None)
+            .unwrap();
+        let intact = std::fs::read(&log_path).unwrap();
+        assert_eq!(logger.repair().unwrap(), 0);
+        assert_eq!(std::fs::read(&log_path).unwrap(), intact);
+
+        // A crash partway through appending a third entry
+        let partial = b"{\"timestamp\": 3, \"event\": \"rej";
+        let mut file = OpenOptions::new().append(true).open(&log_path).unwrap();
+        file.write_all(partial).unwrap();
+        drop(file);
+        assert_eq!(logger.repair().unwrap(), partial.len() as u64);
+        assert_eq!(std::fs::read(&log_path).unwrap(), intact);
+
+        // A complete entry missing only its newline is kept
+        let last_line = intact.split(|&b| b == b'\n').nth(1).unwrap().to_vec();
+        std::fs::write(&log_path, [&intact[..], &last_line].concat()).unwrap();
+        assert_eq!(logger.repair().unwrap(), 0);
+        logger.log_generation("hero", "run", 2).unwrap();
+        assert_eq!(
+            logger
+                .iter_entries()
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .len(),
+            4
+        );
+
+        // A partial line longer than one read chunk
+        let long = format!("{{\"character\": \"{}", "x".repeat(10_000));
+        let before = std::fs::read(&log_path).unwrap();
+        std::fs::write(&log_path, [&before[..], long.as_bytes()].concat()).unwrap();
+        assert_eq!(logger.repair().unwrap(), long.len() as u64);
+        assert_eq!(std::fs::read(&log_path).unwrap(), before);
+    }
+
+    #[test]
+    fn test_concurrent_appenders_write_whole_lines() {
+        const WRITERS: usize = 8;
+        const ENTRIES: usize = 50;
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("feedback.jsonl");
+
+        // A logger each, like separate processes: only the file lock orders them
+        std::thread::scope(|scope| {
+            for writer in 0..WRITERS {
+                let log_path = log_path.clone();
+                scope.spawn(move || {
+                    let logger = FeedbackLogger::with_path(log_path).unwrap();
+                    let character = format!("character-{writer}-{}", "x".repeat(512));
+                    for frame in 0..ENTRIES {
+                        logger
+                            .log_acceptance(frame as u32, &character, "walk", true, Some(0.9), None)
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let contents = std::fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), WRITERS * ENTRIES);
+        for line in lines {
+            serde_json::from_str::<FeedbackEntry>(line).unwrap();
+        }
+        let logger = FeedbackLogger::with_path(log_path).unwrap();
+        assert_eq!(
+            logger.get_stats(None, None).unwrap().accepted,
+            (WRITERS * ENTRIES) as u32
+        );
+    }
+
+    #[test]
+    fn test_append_waits_for_file_lock() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("feedback.jsonl");
+        std::fs::write(&log_path, "").unwrap();
+
+        // Another process reading the log holds a shared lock on its own handle
+        let reader = File::open(&log_path).unwrap();
+        FileExt::lock_shared(&reader).unwrap();
+
+        let logger = FeedbackLogger::with_path(log_path.clone()).unwrap();
+        std::thread::scope(|scope| {
+            let writer = scope.spawn(|| {
+                logger
+                    .log_acceptance(1, "hero", "walk", true, Some(0.9), None)
+                    .unwrap();
+            });
+            // However long this takes, the writer can't finish while the lock is held;
+            // the pause only gives it the chance to get that far
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            assert!(!writer.is_finished());
+            assert!(std::fs::read_to_string(&log_path).unwrap().is_empty());
+
+            FileExt::unlock(&reader).unwrap();
+            writer.join().unwrap();
+        });
+        assert_eq!(logger.get_stats(None, None).unwrap().accepted, 1);
+    }
+
+    #[test]
+    fn test_logging_while_iterating_does_not_deadlock() {
+        let dir = tempdir().unwrap();
+        let logger = FeedbackLogger::with_path(dir.path().join("feedback.jsonl")).unwrap();
+        logger
+            .log_acceptance(1, "hero", "walk", true, Some(0.9), None)
+            .unwrap();
+        logger
+            .log_acceptance(2, "hero", "walk", true, Some(0.9), None)
+            .unwrap();
+
+        // Appending takes an exclusive lock, which would wait forever on a shared lock
+        // still held by the iterator
+        let mut seen = 0;
+        for entry in logger.iter_entries() {
+            let entry = entry.unwrap();
+            logger
+                .log_rejection(entry.frame_number.unwrap(), "hero", "walk", &[], None, None)
+                .unwrap();
+            seen += 1;
+        }
+        assert_eq!(seen, 2);
+        let stats = logger.get_stats(None, None).unwrap();
+        assert_eq!((stats.accepted, stats.rejected), (2, 2));
+    }
+
+    #[test]
+    fn test_stats_by_frame_position() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("test_feedback.jsonl");
+        let logger = FeedbackLogger::with_path(log_path.clone()).unwrap();
+
+        // Three 5-frame generations: the ends are kept, the middle frame is not
+        for _ in 0..3 {
+            for frame in 0..5 {
+                if frame == 2 {
+                    logger
+                        .log_rejection(
+                            frame,
+                            "hero",
+                            "walk",
+                            &[IssueCategory::Ghosting],
+                            None,
+                            Some(5),
+                        )
+                        .unwrap();
+                } else {
+                    logger
+                        .log_acceptance(frame, "hero", "walk", false, None, Some(5))
+                        .unwrap();
+                }
+            }
+        }
+        // An entry from before total_frames was recorded still counts overall
+        let mut file = OpenOptions::new().append(true).open(&log_path).unwrap();
+        writeln!(
+            file,
+            r#"{{"timestamp":1704067200,"event":"reject","character":"hero","motion_type":"walk","frame_number":2,"auto_accepted":null,"issues":[],"confidence_score":null}}"#
+        )
+        .unwrap();
+
+        let stats = logger.get_stats(None, None).unwrap();
+        assert_eq!((stats.accepted, stats.rejected), (12, 4));
+        let bands: Vec<&str> = stats.by_position.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(
+            bands,
+            [
+                "0.00-0.20",
+                "0.20-0.40",
+                "0.40-0.60",
+                "0.60-0.80",
+                "0.80-1.00"
+            ]
+        );
+        let middle = &stats.by_position[2];
+        assert_eq!((middle.accepted, middle.rejected), (0, 3));
+        assert!(stats.by_position[0].acceptance_rate > 0.99);
+    }
 }
@@ -0,0 +1,191 @@
+use crate::confidence::coverage_map;
+use crate::diff::heat_color;
+use image::{imageops::FilterType, DynamicImage, GenericImageView, Rgba, RgbaImage};
+use serde::Serialize;
+
+/// Longest side the frames are matched at; finer detail doesn't change the coarse field
+const ANALYSIS_SIZE: u32 = 256;
+/// Cells with less ink than this (mean coverage, 0-255) have nothing to track
+const MIN_INK: u32 = 8;
+
+/// Estimated displacement of one grid cell from frame A to frame B, in frame A pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FlowVector {
+    /// Cell center in frame A
+    pub x: f32,
+    pub y: f32,
+    pub dx: f32,
+    pub dy: f32,
+}
+
+impl FlowVector {
+    pub fn magnitude(&self) -> f32 {
+        self.dx.hypot(self.dy)
+    }
+}
+
+/// Coarse motion field by block matching: each `spacing`-pixel cell of A that holds
+/// any ink is looked up in B within twice its size, and the best match's offset kept.
+///
+/// Ties (e.g. along a straight line) go to the smallest displacement. B is resized to
+/// A's dimensions if they differ.
+pub fn block_flow(img_a: &DynamicImage, img_b: &DynamicImage, spacing: u32) -> Vec<FlowVector> {
+    let (width, height) = img_a.dimensions();
+    let scale = (ANALYSIS_SIZE as f32 / width.max(height) as f32).min(1.0);
+    let w = ((width as f32 * scale).round() as u32).max(1);
+    let h = ((height as f32 * scale).round() as u32).max(1);
+    let a = coverage_map(&img_a.resize_exact(w, h, FilterType::Triangle));
+    let b = coverage_map(&img_b.resize_exact(w, h, FilterType::Triangle));
+
+    let cell = ((spacing as f32 * scale).round() as u32).max(2);
+    let search = i64::from((cell * 2).max(8));
+    let at = |map: &[u8], x: i64, y: i64| {
+        if x < 0 || y < 0 || x >= i64::from(w) || y >= i64::from(h) {
+            0
+        } else {
+            i64::from(map[(y * i64::from(w) + x) as usize])
+        }
+    };
+
+    let mut flow = Vec::new();
+    for cy in (0..h.saturating_sub(cell - 1)).step_by(cell as usize) {
+        for cx in (0..w.saturating_sub(cell - 1)).step_by(cell as usize) {
+            let pixels: Vec<(i64, i64)> = (cy..cy + cell)
+                .flat_map(|y| (cx..cx + cell).map(move |x| (i64::from(x), i64::from(y))))
+                .collect();
+            let ink: i64 = pixels.iter().map(|&(x, y)| at(&a, x, y)).sum();
+            if ink < i64::from(MIN_INK * cell * cell) {
+                continue;
+            }
+
+            let mut best = (i64::MAX, 0, 0, 0);
+            for dy in -search..=search {
+                for dx in -search..=search {
+                    let cost: i64 = pixels
+                        .iter()
+                        .map(|&(x, y)| (at(&a, x, y) - at(&b, x + dx, y + dy)).abs())
+                        .sum();
+                    let distance = dx * dx + dy * dy;
+                    if (cost, distance) < (best.0, best.1) {
+                        best = (cost, distance, dx, dy);
+                    }
+                }
+            }
+
+            let center = |start: u32| (start as f32 + cell as f32 / 2.0) / scale;
+            flow.push(FlowVector {
+                x: center(cx),
+                y: center(cy),
+                dx: best.2 as f32 / scale,
+                dy: best.3 as f32 / scale,
+            });
+        }
+    }
+    flow
+}
+
+/// Draw the motion field as arrows over a faded copy of frame A, colored from blue
+/// (slowest) to red (fastest). Cells that didn't move get a dot.
+pub fn draw_flow(frame_a: &DynamicImage, flow: &[FlowVector]) -> RgbaImage {
+    // Fade A toward white so the arrows stand out
+    let mut overlay = RgbaImage::from_fn(frame_a.width(), frame_a.height(), |x, y| {
+        let [r, g, b, a] = frame_a.get_pixel(x, y).0;
+        let ink = f32::from(a) / 255.0 * 0.4;
+        let fade = |c: u8| (f32::from(c) * ink + 255.0 * (1.0 - ink)).round() as u8;
+        Rgba([fade(r), fade(g), fade(b), 255])
+    });
+
+    let fastest = flow.iter().map(FlowVector::magnitude).fold(1.0, f32::max);
+    for vector in flow {
+        let [r, g, b] = heat_color(vector.magnitude() / fastest).0;
+        let color = Rgba([r, g, b, 255]);
+        let (x0, y0) = (vector.x, vector.y);
+        if vector.magnitude() < 1.0 {
+            draw_line(&mut overlay, (x0 - 1.0, y0), (x0 + 1.0, y0), color);
+            continue;
+        }
+
+        let (x1, y1) = (x0 + vector.dx, y0 + vector.dy);
+        draw_line(&mut overlay, (x0, y0), (x1, y1), color);
+        let head = (vector.magnitude() * 0.35).clamp(3.0, 8.0);
+        let angle = vector.dy.atan2(vector.dx);
+        for side in [-1.0, 1.0] {
+            let barb = angle + std::f32::consts::PI + side * 0.5;
+            let tip = (x1 + head * barb.cos(), y1 + head * barb.sin());
+            draw_line(&mut overlay, (x1, y1), tip, color);
+        }
+    }
+    overlay
+}
+
+/// Two-pixel-wide line, clipped to the image
+fn draw_line(image: &mut RgbaImage, from: (f32, f32), to: (f32, f32), color: Rgba<u8>) {
+    let steps = (to.0 - from.0)
+        .abs()
+        .max((to.1 - from.1).abs())
+        .ceil()
+        .max(1.0) as u32;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let x = (from.0 + (to.0 - from.0) * t).round() as i64;
+        let y = (from.1 + (to.1 - from.1) * t).round() as i64;
+        for (px, py) in [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)] {
+            if px >= 0 && py >= 0 && px < i64::from(image.width()) && py < i64::from(image.height())
+            {
+                image.put_pixel(px as u32, py as u32, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A patch of scattered ink on a transparent background with its left edge at `x`.
+    /// The scatter gives every cell a unique match; flat fills and straight lines only
+    /// pin down motion across them.
+    fn patch_at(x: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(96, 64, |px, py| {
+            let (u, v) = (px.wrapping_sub(x), py);
+            let mut hash = u.wrapping_mul(73_856_093) ^ v.wrapping_mul(19_349_663);
+            hash ^= hash >> 13;
+            hash = hash.wrapping_mul(0x5bd1_e995);
+            hash ^= hash >> 15;
+            if u < 24 && (20..44).contains(&v) && hash % 5 < 2 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_shifted_pair_points_right() {
+        let (frame_a, frame_b) = (patch_at(20), patch_at(32));
+        let flow = block_flow(&frame_a, &frame_b, 8);
+
+        assert!(!flow.is_empty());
+        for vector in &flow {
+            assert!((vector.dx - 12.0).abs() < 0.5, "{vector:?}");
+            assert!(vector.dy.abs() < 0.5, "{vector:?}");
+        }
+
+        // Arrow pixels are drawn in heatmap colors, which the faded frame never contains
+        let overlay = draw_flow(&frame_a, &flow);
+        let arrows = overlay
+            .pixels()
+            .filter(|pixel| pixel[0] != pixel[1] || pixel[1] != pixel[2])
+            .count();
+        assert!(arrows > 0);
+        // Arrows run from each cell center toward larger x
+        let rightmost = overlay
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel[0] != pixel[1] || pixel[1] != pixel[2])
+            .map(|(x, _, _)| x)
+            .max()
+            .unwrap();
+        let last_center = flow.iter().map(|v| v.x).fold(0.0, f32::max);
+        assert!(rightmost as f32 > last_center + 8.0);
+    }
+}
@@ -1,19 +1,70 @@
 pub mod api;
+pub mod batch;
+pub mod cache;
+pub mod color;
 pub mod config;
 pub mod confidence;
+pub mod diff;
+pub mod easing;
 pub mod feedback;
+pub mod flow;
+pub mod metrics;
+pub mod output;
 pub mod preprocessing;
+pub mod similarity;
+pub mod stats_cache;
 
-pub use api::ApiClient;
-pub use config::Config;
-pub use confidence::{ConfidenceScorer, detect_motion_type};
-pub use feedback::{FeedbackLogger, Statistics};
+#[cfg(test)]
+mod test_util;
+
+pub use api::{ApiClient, Backend, BackendRequest, StageTimer};
+pub use cache::GenerationCache;
+pub use confidence::{
+    classify_motion, detect_motion_type, is_grayscale, ConfidenceScorer, Heuristic, ImageStats,
+    References, ScoreBreakdown, ScoringContext,
+};
+pub use config::{
+    BackgroundKey, ColorSpace, Config, ConfigBuilder, ConfigReport, Connectivity, KeyColor,
+    MotionThresholds, RetryOnLowConfidence,
+};
+pub use easing::Easing;
+pub use feedback::{FeedbackLogger, GroupStats, IssueCategory, Statistics, ThresholdSimulation};
+pub use metrics::Metrics;
+pub use output::OutputWriter;
 pub use preprocessing::{PaddingInfo, Preprocessor};
 
-use anyhow::Result;
-use image::{DynamicImage, GenericImageView};
+use anyhow::{Context, Result};
+use image::{DynamicImage, GenericImageView, RgbaImage};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::borrow::Cow;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Keyframes closer than this (mean pixel difference) leave nothing to interpolate
+const IDENTICAL_KEYFRAMES_DIFF: f32 = 0.005;
+
+/// One generation's settings, shared by every attempt at it
+#[derive(Clone, Copy)]
+struct GenerationRequest<'a> {
+    num_frames: u32,
+    character: Option<&'a str>,
+    motion_type: Option<&'a str>,
+    style_strength: Option<f32>,
+    /// Model seed (`None` lets the backend pick)
+    seed: Option<i64>,
+}
+
+/// The feedback log `config` points at, locked as configured
+fn open_feedback_log(config: &Config) -> Result<FeedbackLogger> {
+    let logger = match &config.feedback_log_path {
+        Some(path) => FeedbackLogger::with_path(path.into())?,
+        None => FeedbackLogger::new()?,
+    };
+    Ok(logger.with_file_locking(config.lock_feedback_log))
+}
 
 /// Main generator struct that orchestrates the entire workflow
 pub struct Generator {
@@ -22,14 +73,28 @@ pub struct Generator {
     preprocessor: Preprocessor,
     confidence_scorer: ConfidenceScorer,
     feedback_logger: FeedbackLogger,
+    cache: Option<GenerationCache>,
+    metrics: Metrics,
+    easing: Easing,
+    reference_mid: Option<DynamicImage>,
 }
 
 impl Generator {
     pub fn new(config: Config) -> Result<Self> {
         let api_client = ApiClient::new(&config.api)?;
         let preprocessor = Preprocessor::new(&config.preprocessing);
-        let confidence_scorer = ConfidenceScorer::new(config.auto_accept_threshold);
-        let feedback_logger = FeedbackLogger::new()?;
+        // The scorer reads the same log the generator writes, for historical_success
+        let confidence_scorer = ConfidenceScorer::new(config.auto_accept_threshold)
+            .with_alpha_threshold(config.preprocessing.alpha_threshold)
+            .with_reject_threshold(config.reject_threshold)
+            .with_config(config.confidence.clone())
+            .with_feedback_logger(open_feedback_log(&config)?);
+        let feedback_logger = open_feedback_log(&config)?;
+        let cache = config
+            .cache_dir
+            .as_ref()
+            .map(GenerationCache::new)
+            .transpose()?;
 
         Ok(Self {
             config,
@@ -37,10 +102,15 @@ impl Generator {
             preprocessor,
             confidence_scorer,
             feedback_logger,
+            cache,
+            metrics: Metrics::new(),
+            easing: Easing::default(),
+            reference_mid: None,
         })
     }
 
-    /// Generate inbetween frames from two keyframes
+    /// Generate inbetween frames from two keyframes. `style_strength` overrides the
+    /// configured value for this call only.
     pub fn generate_inbetweens(
         &self,
         frame_a_path: &Path,
@@ -48,92 +118,807 @@ impl Generator {
         num_frames: u32,
         character: Option<&str>,
         motion_type: Option<&str>,
+        style_strength: Option<f32>,
     ) -> Result<GenerationResult> {
         log::info!(
-            "Generating {} inbetweens between {:?} and {:?}",
+            "Generating {} inbetweens between {} and {}",
             num_frames,
-            frame_a_path,
-            frame_b_path
+            frame_a_path.display(),
+            frame_b_path.display()
         );
 
         // 1. Load images
-        let img_a = image::open(frame_a_path)?;
-        let img_b = image::open(frame_b_path)?;
+        let img_a = load_keyframe(frame_a_path)?;
+        let img_b = load_keyframe(frame_b_path)?;
+
+        self.generate_inbetweens_from_images(
+            &img_a,
+            &img_b,
+            num_frames,
+            character,
+            motion_type,
+            style_strength,
+        )
+    }
+
+    /// Generate inbetween frames from two keyframes already in memory. With
+    /// `retry_on_low_confidence` enabled, a sequence scoring below its floor is
+    /// generated again with fresh seeds and the best attempt kept.
+    pub fn generate_inbetweens_from_images(
+        &self,
+        img_a: &DynamicImage,
+        img_b: &DynamicImage,
+        num_frames: u32,
+        character: Option<&str>,
+        motion_type: Option<&str>,
+        style_strength: Option<f32>,
+    ) -> Result<GenerationResult> {
+        if num_frames == 0 {
+            anyhow::bail!("Asked for 0 inbetween frames; request at least 1");
+        }
+        if num_frames > self.config.max_num_frames {
+            anyhow::bail!(
+                "Asked for {} inbetween frames, more than the limit of {} \
+                 (raise max_num_frames in the config if this is intended)",
+                num_frames,
+                self.config.max_num_frames
+            );
+        }
+
+        let request = GenerationRequest {
+            num_frames,
+            character,
+            motion_type,
+            style_strength,
+            seed: self.api_client.seed(),
+        };
+        let first = self.generate_attempt(img_a, img_b, &request)?;
+        let result = self.retry_low_confidence(img_a, img_b, request, first);
+
+        // 7. Log generation
+        self.metrics.record_generation();
+        self.record_frames(&result);
+        self.feedback_logger.log_generation(
+            character.unwrap_or("unknown"),
+            result.metadata.motion_type.as_deref().unwrap_or("unknown"),
+            num_frames,
+        )?;
+
+        Ok(result)
+    }
+
+    /// Count the kept frames' scores in the metrics. Attempts a retry discarded are
+    /// left out, so the metrics describe what was delivered.
+    fn record_frames(&self, result: &GenerationResult) {
+        for frame in result.frames.iter().filter(|f| f.scoring_error.is_none()) {
+            self.metrics.record_frame(frame.score, frame.near_duplicate);
+        }
+    }
+
+    /// Generate again with fresh seeds while the best sequence so far has a mean
+    /// confidence below `retry_on_low_confidence.mean_threshold`, up to `max_retries`
+    /// times. A retry that fails keeps the frames already generated. A seed the user
+    /// fixed is never swapped out, so there are no retries then.
+    fn retry_low_confidence(
+        &self,
+        img_a: &DynamicImage,
+        img_b: &DynamicImage,
+        mut request: GenerationRequest,
+        first: GenerationResult,
+    ) -> GenerationResult {
+        let retry = &self.config.retry_on_low_confidence;
+        let mut best = first;
+        if !retry.enabled {
+            return best;
+        }
+        if let Some(seed) = request.seed {
+            if best.sequence_score() < retry.mean_threshold {
+                log::warn!(
+                    "Mean confidence {:.2} is below {:.2}, but seed {} was fixed; not retrying \
+                     with other seeds",
+                    best.sequence_score(),
+                    retry.mean_threshold,
+                    seed
+                );
+            }
+            return best;
+        }
+
+        for attempt in 1..=retry.max_retries {
+            let mean = best.sequence_score();
+            if mean >= retry.mean_threshold {
+                break;
+            }
+            if self.is_cancelled() {
+                log::warn!("Canceled; skipping the remaining retries");
+                break;
+            }
+            let seed = i64::from(rand::random::<u32>());
+            log::warn!(
+                "Mean confidence {:.2} is below {:.2} (seed {}); retrying with seed {} ({}/{})",
+                mean,
+                retry.mean_threshold,
+                best.metadata
+                    .seed
+                    .map_or_else(|| "picked by the backend".to_string(), |s| s.to_string()),
+                seed,
+                attempt,
+                retry.max_retries
+            );
+
+            request.seed = Some(seed);
+            match self.generate_attempt(img_a, img_b, &request) {
+                Ok(result) => {
+                    log::info!(
+                        "Seed {} scored a mean confidence of {:.2}",
+                        seed,
+                        result.sequence_score()
+                    );
+                    if result.sequence_score() > best.sequence_score() {
+                        best = result;
+                    }
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Retry with seed {} failed ({:#}); keeping the best so far",
+                        seed,
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    /// One generation of `request`'s frames, preprocessing through scoring
+    fn generate_attempt(
+        &self,
+        img_a: &DynamicImage,
+        img_b: &DynamicImage,
+        request: &GenerationRequest,
+    ) -> Result<GenerationResult> {
+        let GenerationRequest {
+            num_frames,
+            character,
+            motion_type,
+            style_strength,
+            seed,
+        } = *request;
+        let started = Instant::now();
 
         // Store original dimensions for potential restoration
         let (orig_width, orig_height) = img_a.dimensions();
         let padding_info = self.preprocessor.get_padding_info(orig_width, orig_height);
 
         // 2. Preprocess
-        let cleaned_a = self.preprocessor.process(&img_a)?;
-        let cleaned_b = self.preprocessor.process(&img_b)?;
+        let cleaned_a = self.preprocessor.process(img_a)?;
+        let cleaned_b = self.preprocessor.process(&align_keyframe(img_a, img_b))?;
+        self.warn_if_identical(&cleaned_a, &cleaned_b);
+        let cleaned_mid = self.clean_reference_mid(img_a)?;
 
         // 3. Auto-detect motion type if not provided
-        let detected_motion = motion_type
-            .map(String::from)
-            .unwrap_or_else(|| detect_motion_type(&cleaned_a, &cleaned_b));
+        let detected_motion = motion_type.map_or_else(
+            || {
+                detect_motion_type(
+                    &cleaned_a,
+                    &cleaned_b,
+                    &self.config.motion_thresholds,
+                    self.config.preprocessing.alpha_threshold,
+                )
+            },
+            String::from,
+        );
 
         log::info!("Motion type: {}", detected_motion);
+        let preprocess_time = started.elapsed();
 
-        // 4. Call API
-        let generated = self
-            .api_client
-            .generate_inbetweens(&cleaned_a, &cleaned_b, num_frames)?;
+        // 4. Call API (or reuse an identical earlier request)
+        let api_started = Instant::now();
+        let download = StageTimer::default();
+        let generated = self.fetch_frames(
+            &cleaned_a,
+            &cleaned_b,
+            num_frames,
+            style_strength,
+            seed,
+            &download,
+        )?;
+        let api_time = api_started.elapsed().saturating_sub(download.elapsed());
 
         log::info!("API returned {} frames", generated.len());
 
-        // 5. Score confidence for each frame
+        // 5. Blend in the positions a short render left out, if asked to
+        let (generated, mut synthesized) =
+            self.fill_short_render(generated, &cleaned_a, &cleaned_b, num_frames);
+
+        // 6. Flag or drop frames that just repeat the one before; recolor if asked to
+        let returned = generated.len();
+        let (generated, repeats, easing) = self.handle_repeats(generated, &mut synthesized);
+        let generated = self.recolor_frames(generated, &cleaned_a, &cleaned_b, &easing);
+        let frame_count_note = FrameCountNote::shortfall(num_frames, returned, generated.len());
+        if let Some(note) = &frame_count_note {
+            log::warn!("{}", note);
+        }
+
+        // 7. Score confidence for each frame
+        let score_started = Instant::now();
+        let references = References {
+            source_a: &cleaned_a,
+            source_b: &cleaned_b,
+            mid: cleaned_mid.as_ref(),
+        };
+        let scores =
+            self.score_sequence(&generated, references, &detected_motion, character, &easing);
+        let score_time = score_started.elapsed();
         let mut scored_frames = Vec::new();
-        for (i, frame) in generated.into_iter().enumerate() {
-            let score = self.confidence_scorer.score_frame(
-                &frame,
-                &cleaned_a,
-                &cleaned_b,
-                &detected_motion,
-                character,
-            )?;
+        for (((frame, score), repeats_previous), synthesized) in generated
+            .into_iter()
+            .zip(scores)
+            .zip(repeats)
+            .zip(synthesized)
+        {
+            let final_frame = self.restore_size(frame, &padding_info, orig_width, orig_height);
+            scored_frames.push(ScoredFrame {
+                synthesized,
+                ..self.scored_frame(final_frame, score, repeats_previous)
+            });
+        }
 
-            log::debug!("Frame {} confidence: {:.2}", i, score);
+        Ok(GenerationResult {
+            frames: scored_frames,
+            metadata: GenerationMetadata {
+                character: character.map(String::from),
+                motion_type: Some(detected_motion),
+                auto_accept_threshold: self.config.auto_accept_threshold,
+                original_width: orig_width,
+                original_height: orig_height,
+                seed,
+                style_strength: self.api_client.style_strength(style_strength),
+                model_version: self.api_client.model_version(),
+                easing,
+                frame_count_note,
+            },
+            timings: GenerationTimings {
+                preprocess_ms: Some(millis(preprocess_time)),
+                api_ms: Some(millis(api_time)),
+                download_ms: Some(millis(download.elapsed())),
+                score_ms: Some(millis(score_time)),
+                total_ms: Some(millis(started.elapsed())),
+            },
+        })
+    }
 
-            // Optionally restore original dimensions
-            let final_frame = if self.config.preprocessing.normalize_resolution {
-                self.preprocessor.restore_original_size(
-                    &frame,
-                    &padding_info,
-                    orig_width,
-                    orig_height,
+    /// Re-score frames already on disk (e.g. after hand edits) against their keyframes,
+    /// without calling the API. Frames are taken to be in sequence order.
+    pub fn rescore_frames(
+        &self,
+        frame_paths: &[PathBuf],
+        frame_a_path: &Path,
+        frame_b_path: &Path,
+        character: Option<&str>,
+        motion_type: Option<&str>,
+    ) -> Result<GenerationResult> {
+        let started = Instant::now();
+        let img_a = load_keyframe(frame_a_path)?;
+        let img_b = load_keyframe(frame_b_path)?;
+        let (orig_width, orig_height) = img_a.dimensions();
+
+        let cleaned_a = self.preprocessor.process(&img_a)?;
+        let cleaned_b = self.preprocessor.process(&align_keyframe(&img_a, &img_b))?;
+        let cleaned_mid = self.clean_reference_mid(&img_a)?;
+        let detected_motion = motion_type.map_or_else(
+            || {
+                detect_motion_type(
+                    &cleaned_a,
+                    &cleaned_b,
+                    &self.config.motion_thresholds,
+                    self.config.preprocessing.alpha_threshold,
                 )
-            } else {
-                frame
-            };
+            },
+            String::from,
+        );
 
-            scored_frames.push(ScoredFrame {
-                frame: final_frame,
-                score,
-                auto_accept: self.confidence_scorer.should_auto_accept(score),
-            });
+        let mut frames = Vec::with_capacity(frame_paths.len());
+        let mut cleaned_frames = Vec::with_capacity(frame_paths.len());
+        for path in frame_paths {
+            let frame = image::open(path)
+                .with_context(|| format!("Failed to load frame {}", path.display()))?;
+            // Same space the model's output is scored in
+            cleaned_frames.push(self.preprocessor.process(&frame)?);
+            frames.push(frame);
         }
+        let preprocess_time = started.elapsed();
 
-        // 6. Log generation
-        self.feedback_logger.log_generation(
-            character.unwrap_or("unknown"),
+        let score_started = Instant::now();
+        let references = References {
+            source_a: &cleaned_a,
+            source_b: &cleaned_b,
+            mid: cleaned_mid.as_ref(),
+        };
+        let scores = self.score_sequence(
+            &cleaned_frames,
+            references,
             &detected_motion,
-            num_frames,
-        )?;
+            character,
+            &self.easing,
+        );
+        let score_time = score_started.elapsed();
 
-        Ok(GenerationResult {
-            frames: scored_frames,
+        let result = GenerationResult {
+            frames: frames
+                .into_iter()
+                .zip(scores)
+                .map(|(frame, score)| self.scored_frame(frame, score, false))
+                .collect(),
             metadata: GenerationMetadata {
                 character: character.map(String::from),
                 motion_type: Some(detected_motion),
                 auto_accept_threshold: self.config.auto_accept_threshold,
                 original_width: orig_width,
                 original_height: orig_height,
+                seed: None,
+                style_strength: None,
+                model_version: None,
+                easing: self.easing.clone(),
+                frame_count_note: None,
+            },
+            // Frames come from disk, so there is no API or download stage
+            timings: GenerationTimings {
+                preprocess_ms: Some(millis(preprocess_time)),
+                score_ms: Some(millis(score_time)),
+                total_ms: Some(millis(started.elapsed())),
+                ..GenerationTimings::default()
             },
+        };
+        self.record_frames(&result);
+        Ok(result)
+    }
+
+    /// Identical keyframes usually mean the same file was passed twice, or a cycle's
+    /// start was given where a distinct pose was meant. Generation still goes ahead.
+    fn warn_if_identical(&self, cleaned_a: &DynamicImage, cleaned_b: &DynamicImage) {
+        let diff = confidence::calculate_pixel_difference(
+            cleaned_a,
+            cleaned_b,
+            self.config.confidence.diff_samples,
+            self.config.preprocessing.alpha_threshold,
+        );
+        if diff < IDENTICAL_KEYFRAMES_DIFF {
+            log::warn!(
+                "Keyframes are nearly identical (difference {:.4}); nothing to interpolate. \
+                 Check that two distinct poses were given.",
+                diff
+            );
+        }
+    }
+
+    /// Find frames that repeat the one before them (e.g. a backend padding out more
+    /// frames than it can make). Repeats are dropped if configured, the remaining frames
+    /// keeping their original positions, and flagged otherwise. Returns the frames,
+    /// which of them are repeats, and their spacing.
+    fn handle_repeats(
+        &self,
+        frames: Vec<DynamicImage>,
+        synthesized: &mut Vec<bool>,
+    ) -> (Vec<DynamicImage>, Vec<bool>, Easing) {
+        let Some(distance) = self.config.repeated_frame_distance else {
+            let count = frames.len();
+            return (frames, vec![false; count], self.easing.clone());
+        };
+
+        let repeats = similarity::consecutive_repeats(&frames, distance);
+        let repeated = repeats.iter().filter(|r| **r).count();
+        if repeated == 0 || !self.config.drop_repeated_frames {
+            if repeated > 0 {
+                log::warn!("{} frame(s) repeat the frame before them", repeated);
+            }
+            return (frames, repeats, self.easing.clone());
+        }
+
+        log::warn!(
+            "Dropping {} frame(s) that repeat the frame before them",
+            repeated
+        );
+        let positions = self.easing.positions(frames.len());
+        let mut dropped = repeats.iter();
+        synthesized.retain(|_| !dropped.next().copied().unwrap_or(false));
+        let (kept, positions): (Vec<_>, Vec<_>) = frames
+            .into_iter()
+            .zip(positions)
+            .zip(&repeats)
+            .filter(|(_, repeat)| !**repeat)
+            .map(|(frame, _)| frame)
+            .unzip();
+        let count = kept.len();
+        (kept, vec![false; count], Easing::Custom(positions))
+    }
+
+    /// With `upsample_when_short`, blend the positions a render with fewer than
+    /// `num_frames` frames left out. Returns the frames and which of them were blended.
+    fn fill_short_render(
+        &self,
+        frames: Vec<DynamicImage>,
+        cleaned_a: &DynamicImage,
+        cleaned_b: &DynamicImage,
+        num_frames: u32,
+    ) -> (Vec<DynamicImage>, Vec<bool>) {
+        let native = frames.len();
+        if !self.config.upsample_when_short || native >= num_frames as usize {
+            return (frames, vec![false; native]);
+        }
+
+        let (frames, synthesized) = api::upsample_evenly(frames, cleaned_a, cleaned_b, num_frames);
+        log::warn!(
+            "Model produced only {} native frames; blended {} more to reach {}",
+            native,
+            synthesized.iter().filter(|blended| **blended).count(),
+            num_frames
+        );
+        (frames, synthesized)
+    }
+
+    /// Undo resolution normalization, if it was applied
+    fn restore_size(
+        &self,
+        frame: DynamicImage,
+        padding_info: &PaddingInfo,
+        orig_width: u32,
+        orig_height: u32,
+    ) -> DynamicImage {
+        if self.config.preprocessing.normalize_resolution {
+            self.preprocessor
+                .restore_original_size(&frame, padding_info, orig_width, orig_height)
+        } else {
+            frame
+        }
+    }
+
+    /// With `interpolate_alpha_only`, replace each frame's color with the keyframes'
+    /// mixed at the frame's position, keeping the generated alpha
+    fn recolor_frames(
+        &self,
+        frames: Vec<DynamicImage>,
+        cleaned_a: &DynamicImage,
+        cleaned_b: &DynamicImage,
+        easing: &Easing,
+    ) -> Vec<DynamicImage> {
+        if !self.config.interpolate_alpha_only {
+            return frames;
+        }
+        let positions = easing.positions(frames.len());
+        frames
+            .iter()
+            .zip(positions)
+            .map(|(frame, t)| preprocessing::recolor_from_sources(frame, cleaned_a, cleaned_b, t))
+            .collect()
+    }
+
+    /// The midpoint reference preprocessed like keyframe B, if one is set
+    fn clean_reference_mid(&self, img_a: &DynamicImage) -> Result<Option<DynamicImage>> {
+        self.reference_mid
+            .as_ref()
+            .map(|mid| self.preprocessor.process(&align_keyframe(img_a, mid)))
+            .transpose()
+    }
+
+    /// Score frames at their positions (per `easing`) between the preprocessed keyframes.
+    ///
+    /// A frame whose scoring fails gets a zero score and the error instead of aborting
+    /// the sequence, so frames already paid for are never lost.
+    fn score_sequence(
+        &self,
+        frames: &[DynamicImage],
+        references: References,
+        motion_type: &str,
+        character: Option<&str>,
+        easing: &Easing,
+    ) -> Vec<FrameScore> {
+        let (cleaned_a, cleaned_b) = (references.source_a, references.source_b);
+        // Hashes of the keyframes, to catch frames the model just copied
+        let keyframe_hashes = [similarity::phash(cleaned_a), similarity::phash(cleaned_b)];
+
+        let mut scores = Vec::with_capacity(frames.len());
+        for (i, frame) in frames.iter().enumerate() {
+            let position = easing.position(i, frames.len());
+            let outcome = self.confidence_scorer.score_against(
+                frame,
+                references,
+                motion_type,
+                character,
+                position,
+            );
+
+            let near_duplicate = self.config.near_duplicate_distance.is_some_and(|distance| {
+                similarity::is_near_duplicate(frame, &keyframe_hashes, distance)
+            });
+            if near_duplicate {
+                log::warn!("Frame {} is a near-duplicate of a keyframe", i);
+            }
+            let aspect_distorted = self.config.aspect_tolerance.is_some_and(|tolerance| {
+                confidence::aspect_deviation(frame, cleaned_a, cleaned_b)
+                    .is_some_and(|deviation| deviation > tolerance)
+            });
+            if aspect_distorted {
+                log::warn!("Frame {} looks stretched compared to the keyframes", i);
+            }
+
+            let (breakdown, error) = match outcome {
+                Ok(breakdown) => {
+                    log::debug!("Frame {} confidence: {:.2}", i, breakdown.score);
+                    (breakdown, None)
+                }
+                Err(e) => {
+                    log::warn!("Could not score frame {}: {:#}", i, e);
+                    let breakdown = ScoreBreakdown {
+                        score: 0.0,
+                        penalties: Vec::new(),
+                    };
+                    (breakdown, Some(format!("{e:#}")))
+                }
+            };
+
+            scores.push(FrameScore {
+                breakdown,
+                near_duplicate,
+                aspect_distorted,
+                error,
+                smoothed: None,
+            });
+        }
+
+        self.smooth_scores(&mut scores);
+        scores
+    }
+
+    /// With `confidence.smooth_scores`, average each frame's score with its neighbours'
+    /// for the accept/reject decision. Frames whose scoring failed are left out of
+    /// every average.
+    fn smooth_scores(&self, scores: &mut [FrameScore]) {
+        let confidence = &self.config.confidence;
+        if !confidence.smooth_scores {
+            return;
+        }
+
+        let half = confidence.smoothing_window / 2;
+        let smoothed: Vec<Option<f32>> = (0..scores.len())
+            .map(|i| {
+                if scores[i].error.is_some() {
+                    return None;
+                }
+                let window = &scores[i.saturating_sub(half)..(i + half + 1).min(scores.len())];
+                let (sum, count) = window
+                    .iter()
+                    .filter(|score| score.error.is_none())
+                    .fold((0.0, 0), |(sum, count), score| {
+                        (sum + score.breakdown.score, count + 1)
+                    });
+                Some(sum / count as f32)
+            })
+            .collect();
+        for (i, (score, smoothed)) in scores.iter_mut().zip(smoothed).enumerate() {
+            if let Some(smoothed) = smoothed {
+                log::debug!(
+                    "Frame {} smoothed confidence: {:.2} (raw {:.2})",
+                    i,
+                    smoothed,
+                    score.breakdown.score
+                );
+            }
+            score.smoothed = smoothed;
+        }
+    }
+
+    fn scored_frame(
+        &self,
+        frame: DynamicImage,
+        score: FrameScore,
+        repeats_previous: bool,
+    ) -> ScoredFrame {
+        let FrameScore {
+            breakdown,
+            near_duplicate,
+            aspect_distorted,
+            error,
+            smoothed,
+        } = score;
+        let decision = smoothed.unwrap_or(breakdown.score);
+
+        ScoredFrame {
+            frame,
+            score: breakdown.score,
+            auto_accept: !near_duplicate
+                && !repeats_previous
+                && !aspect_distorted
+                && error.is_none()
+                && self.confidence_scorer.should_auto_accept(decision),
+            // A failed score says nothing about the frame, so it goes to review
+            auto_reject: error.is_none() && self.confidence_scorer.should_auto_reject(decision),
+            penalties: breakdown.penalties,
+            near_duplicate,
+            repeats_previous,
+            aspect_distorted,
+            scoring_error: error,
+            smoothed_score: smoothed,
+            synthesized: false,
+        }
+    }
+
+    /// Call the API, going through the generation cache when one is configured. Time
+    /// spent fetching the generated frames is added to `download`.
+    fn fetch_frames(
+        &self,
+        frame_a: &DynamicImage,
+        frame_b: &DynamicImage,
+        num_frames: u32,
+        style_strength: Option<f32>,
+        seed: Option<i64>,
+        download: &StageTimer,
+    ) -> Result<Vec<DynamicImage>> {
+        let Some(cache) = &self.cache else {
+            return self.call_api(frame_a, frame_b, num_frames, style_strength, seed, download);
+        };
+
+        let key = self.api_client.request_fingerprint(
+            frame_a,
+            frame_b,
+            num_frames,
+            style_strength,
+            seed,
+        )?;
+        if let Some(frames) = cache.load(&key)? {
+            log::info!("Using cached frames ({})", &key[..12]);
+            self.metrics.record_cache_hit();
+            return Ok(frames);
+        }
+
+        let frames = self.call_api(frame_a, frame_b, num_frames, style_strength, seed, download)?;
+        // The frames are paid for by now; a cache that can't be written only costs a rerun
+        if let Err(e) = cache.store(&key, &frames) {
+            log::warn!("Could not cache frames ({}): {:#}", &key[..12], e);
+        }
+        Ok(frames)
+    }
+
+    fn call_api(
+        &self,
+        frame_a: &DynamicImage,
+        frame_b: &DynamicImage,
+        num_frames: u32,
+        style_strength: Option<f32>,
+        seed: Option<i64>,
+        download: &StageTimer,
+    ) -> Result<Vec<DynamicImage>> {
+        self.api_client
+            .generate_timed(frame_a, frame_b, num_frames, style_strength, seed, download)
+            .map_err(|e| {
+                self.metrics.record_api_error();
+                e
+            })
+    }
+
+    /// Preprocess a keyframe pair and report how hard it looks to inbetween, without
+    /// calling the API
+    pub fn analyze_pair(
+        &self,
+        frame_a_path: &Path,
+        frame_b_path: &Path,
+        character: Option<&str>,
+    ) -> Result<PairAnalysis> {
+        let (cleaned_a, cleaned_b) = self.prepare_pair(frame_a_path, frame_b_path)?;
+        let motion_type = detect_motion_type(
+            &cleaned_a,
+            &cleaned_b,
+            &self.config.motion_thresholds,
+            self.config.preprocessing.alpha_threshold,
+        );
+        let penalties = self.confidence_scorer.predict_source_penalties(
+            &cleaned_a,
+            &cleaned_b,
+            &motion_type,
+            character,
+        );
+
+        Ok(PairAnalysis {
+            pixel_difference: confidence::calculate_pixel_difference(
+                &cleaned_a,
+                &cleaned_b,
+                self.config.confidence.diff_samples,
+                self.config.preprocessing.alpha_threshold,
+            ),
+            frame_a: confidence::calculate_image_stats(
+                &cleaned_a,
+                self.config.confidence.stats_samples,
+                self.config.preprocessing.alpha_threshold,
+            ),
+            frame_b: confidence::calculate_image_stats(
+                &cleaned_b,
+                self.config.confidence.stats_samples,
+                self.config.preprocessing.alpha_threshold,
+            ),
+            motion_type,
+            penalties,
         })
     }
 
-    /// Log acceptance of a frame
+    /// Draw the coarse motion field between two keyframes as arrows over frame A, one
+    /// per `spacing` pixels, to show what the motion classification saw
+    pub fn motion_overlay(
+        &self,
+        frame_a_path: &Path,
+        frame_b_path: &Path,
+        spacing: u32,
+    ) -> Result<RgbaImage> {
+        let (cleaned_a, cleaned_b) = self.prepare_pair(frame_a_path, frame_b_path)?;
+        let flow = flow::block_flow(&cleaned_a, &cleaned_b, spacing);
+        Ok(flow::draw_flow(&cleaned_a, &flow))
+    }
+
+    /// Load and preprocess a keyframe pair the way generation would see it
+    fn prepare_pair(
+        &self,
+        frame_a_path: &Path,
+        frame_b_path: &Path,
+    ) -> Result<(DynamicImage, DynamicImage)> {
+        let img_a = load_keyframe(frame_a_path)?;
+        let cleaned_a = self.preprocessor.process(&img_a)?;
+        let cleaned_b = self
+            .preprocessor
+            .process(&align_keyframe(&img_a, &load_keyframe(frame_b_path)?))?;
+        Ok((cleaned_a, cleaned_b))
+    }
+
+    /// Counters accumulated by this generator, e.g. for a Prometheus scrape
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Generate through a custom backend instead of the one the config names
+    pub fn set_backend(&mut self, backend: impl Backend + 'static) {
+        self.api_client.set_backend(backend);
+    }
+
+    /// Check the configured model exists and is usable before any generation work; see
+    /// [`ApiClient::validate_model`]
+    pub fn validate_model(&self) -> Result<()> {
+        self.api_client.validate_model()
+    }
+
+    /// Stop subsequent generations once `cancel` is set (see [`ApiClient::set_cancel_flag`])
+    pub fn set_cancel_flag(&mut self, cancel: Arc<AtomicBool>) {
+        self.api_client.set_cancel_flag(cancel);
+    }
+
+    /// Whether the cancel flag has been set, so no further generation should start
+    pub fn is_cancelled(&self) -> bool {
+        self.api_client.is_cancelled()
+    }
+
+    /// Fix the model seed for subsequent generations, e.g. to sample several candidates
+    pub fn set_seed(&mut self, seed: Option<i64>) {
+        self.api_client.set_seed(seed);
+    }
+
+    /// Space subsequent sequences along an easing curve rather than evenly, for scoring
+    /// and the positions recorded in metadata
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+
+    /// Judge subsequent sequences' color and structure against this pose at their
+    /// midpoint as well as the keyframes, for motion that doesn't pass through a plain
+    /// blend of them. The reference only affects scoring; it is never generated or sent
+    /// to the backend.
+    pub fn set_reference_mid(&mut self, reference: Option<DynamicImage>) {
+        self.reference_mid = reference;
+    }
+
+    /// Register a custom confidence heuristic alongside the built-in ones
+    pub fn add_heuristic(&mut self, heuristic: impl Heuristic + 'static) {
+        self.confidence_scorer.add_heuristic(heuristic);
+    }
+
+    /// Log acceptance of a frame (see [`FeedbackLogger::log_acceptance`])
     pub fn accept_frame(
         &self,
         frame_number: u32,
@@ -141,22 +926,36 @@ impl Generator {
         motion_type: &str,
         auto: bool,
         confidence: Option<f32>,
+        total_frames: Option<u32>,
     ) -> Result<()> {
-        self.feedback_logger
-            .log_acceptance(frame_number, character, motion_type, auto, confidence)
+        self.feedback_logger.log_acceptance(
+            frame_number,
+            character,
+            motion_type,
+            auto,
+            confidence,
+            total_frames,
+        )
     }
 
-    /// Log rejection of a frame
+    /// Log rejection of a frame (see [`FeedbackLogger::log_rejection`])
     pub fn reject_frame(
         &self,
         frame_number: u32,
         character: &str,
         motion_type: &str,
-        issues: &[String],
+        issues: &[IssueCategory],
         confidence: Option<f32>,
+        total_frames: Option<u32>,
     ) -> Result<()> {
-        self.feedback_logger
-            .log_rejection(frame_number, character, motion_type, issues, confidence)
+        self.feedback_logger.log_rejection(
+            frame_number,
+            character,
+            motion_type,
+            issues,
+            confidence,
+            total_frames,
+        )
     }
 
     /// Get statistics from the feedback log
@@ -169,12 +968,91 @@ impl Generator {
     }
 }
 
+/// Fit keyframe B to A's dimensions if they differ, so both are normalized the same way
+/// and compared like-for-like. Frames are restored to A's size either way.
+fn align_keyframe<'a>(img_a: &DynamicImage, img_b: &'a DynamicImage) -> Cow<'a, DynamicImage> {
+    let (width, height) = img_a.dimensions();
+    if img_b.dimensions() == (width, height) {
+        return Cow::Borrowed(img_b);
+    }
+
+    log::warn!(
+        "Keyframes differ in size ({}x{} vs {}x{}); fitting frame B to frame A",
+        width,
+        height,
+        img_b.width(),
+        img_b.height()
+    );
+    Cow::Owned(preprocessing::letterbox(img_b, width, height))
+}
+
+/// Open a keyframe, naming the file in the error and telling an empty file (usually an
+/// interrupted export) apart from one that doesn't decode
+pub fn load_keyframe(path: &Path) -> Result<DynamicImage> {
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read keyframe {}", path.display()))?
+        .len();
+    if size == 0 {
+        anyhow::bail!(
+            "Keyframe {} is empty (0 bytes); was its export interrupted?",
+            path.display()
+        );
+    }
+    image::open(path).with_context(|| {
+        format!(
+            "Keyframe {} could not be decoded; it may be truncated or not an image",
+            path.display()
+        )
+    })
+}
+
 /// A frame with its confidence score
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct ScoredFrame {
     pub frame: DynamicImage,
     pub score: f32,
     pub auto_accept: bool,
+    /// Scored below the configured reject threshold
+    pub auto_reject: bool,
+    /// Penalty applied by each heuristic, keyed by heuristic name
+    pub penalties: Vec<(String, f32)>,
+    /// Frame is perceptually identical to one of the keyframes (never auto-accepted)
+    pub near_duplicate: bool,
+    /// Frame repeats the one before it (never auto-accepted)
+    pub repeats_previous: bool,
+    /// Frame's content has a different aspect ratio than the keyframes', as if stretched
+    /// (never auto-accepted)
+    pub aspect_distorted: bool,
+    /// Why scoring failed, if it did; the frame is kept with a score of 0.0
+    pub scoring_error: Option<String>,
+    /// Neighbourhood average `auto_accept` and `auto_reject` were decided on, with
+    /// `confidence.smooth_scores`; `None` means they used `score`
+    pub smoothed_score: Option<f32>,
+    /// Blended from neighboring frames to fill a position the model didn't render
+    pub synthesized: bool,
+}
+
+/// What can be predicted about a keyframe pair before generating
+#[derive(Debug, Serialize)]
+pub struct PairAnalysis {
+    pub motion_type: String,
+    /// Normalized difference between the preprocessed keyframes (0.0 = identical)
+    pub pixel_difference: f32,
+    pub frame_a: ImageStats,
+    pub frame_b: ImageStats,
+    /// Penalties from heuristics that only need the keyframes, keyed by heuristic name
+    pub penalties: Vec<(String, f32)>,
+}
+
+/// Outcome of scoring one frame of a sequence
+struct FrameScore {
+    breakdown: ScoreBreakdown,
+    near_duplicate: bool,
+    aspect_distorted: bool,
+    error: Option<String>,
+    /// Neighbourhood average the accept/reject decision uses instead of the raw score
+    smoothed: Option<f32>,
 }
 
 /// Result of a generation operation
@@ -182,6 +1060,45 @@ pub struct ScoredFrame {
 pub struct GenerationResult {
     pub frames: Vec<ScoredFrame>,
     pub metadata: GenerationMetadata,
+    pub timings: GenerationTimings,
+}
+
+/// Where a generation's time went, in milliseconds. Stages that didn't run (e.g. the
+/// API when re-scoring frames from disk) are None.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationTimings {
+    /// Preprocessing the keyframes and detecting the motion type
+    pub preprocess_ms: Option<u64>,
+    /// The backend call minus `download_ms`: uploading and running the model, or
+    /// reading the generation cache on a hit
+    pub api_ms: Option<u64>,
+    /// Fetching the generated frames (downloads, video frame extraction, decoding)
+    pub download_ms: Option<u64>,
+    /// Scoring every frame
+    pub score_ms: Option<u64>,
+    pub total_ms: Option<u64>,
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+impl GenerationResult {
+    /// Mean confidence across the frames that could be scored, used to rank candidate
+    /// sequences. A frame whose scoring failed has no real score, so it is left out.
+    pub fn sequence_score(&self) -> f32 {
+        let scores: Vec<f32> = self
+            .frames
+            .iter()
+            .filter(|f| f.scoring_error.is_none())
+            .map(|f| f.score)
+            .collect();
+        if scores.is_empty() {
+            return 0.0;
+        }
+
+        scores.iter().sum::<f32>() / scores.len() as f32
+    }
 }
 
 /// Metadata about a generation
@@ -192,16 +1109,104 @@ pub struct GenerationMetadata {
     pub auto_accept_threshold: f32,
     pub original_width: u32,
     pub original_height: u32,
+    /// Model seed, if one was fixed
+    pub seed: Option<i64>,
+    /// Style strength sent to the model (None if the backend has no such input)
+    pub style_strength: Option<f32>,
+    /// Model version the frames came from (`owner/name:hash`), if the backend names one
+    #[serde(default)]
+    pub model_version: Option<String>,
+    /// Spacing of the frames between the keyframes
+    #[serde(default)]
+    pub easing: Easing,
+    /// Why fewer frames came back than were asked for, if they did
+    #[serde(default)]
+    pub frame_count_note: Option<FrameCountNote>,
+}
+
+/// A generation that delivered fewer frames than requested, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameCountNote {
+    pub requested: u32,
+    pub delivered: u32,
+    /// e.g. "model produced only 3 native frames"
+    pub reason: String,
+}
+
+impl FrameCountNote {
+    /// Note for `requested` frames of which the backend `returned` some and `delivered`
+    /// survived dropping repeats, or `None` if none went missing
+    fn shortfall(requested: u32, returned: usize, delivered: usize) -> Option<Self> {
+        let mut reasons = Vec::new();
+        if returned < requested as usize {
+            reasons.push(format!("model produced only {returned} native frames"));
+        }
+        if delivered < returned {
+            reasons.push(format!(
+                "{} repeated frame(s) dropped",
+                returned - delivered
+            ));
+        }
+        if reasons.is_empty() {
+            return None;
+        }
+        Some(Self {
+            requested,
+            delivered: u32::try_from(delivered).unwrap_or(u32::MAX),
+            reason: reasons.join("; "),
+        })
+    }
+}
+
+impl fmt::Display for FrameCountNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "requested {} frame(s) but got {}: {}",
+            self.requested, self.delivered, self.reason
+        )
+    }
 }
 
 /// Output metadata written to JSON file
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputMetadata {
     pub character: Option<String>,
     pub motion_type: Option<String>,
     pub confidence_scores: Vec<f32>,
     pub auto_accept: Vec<bool>,
     pub auto_accept_threshold: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(default)]
+    pub near_duplicate: Vec<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style_strength: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+    /// Each frame's position between the keyframes (0.0 = A, 1.0 = B)
+    #[serde(default)]
+    pub positions: Vec<f32>,
+    #[serde(default)]
+    pub repeats_previous: Vec<bool>,
+    #[serde(default)]
+    pub auto_reject: Vec<bool>,
+    #[serde(default)]
+    pub aspect_distorted: Vec<bool>,
+    #[serde(default)]
+    pub synthesized: Vec<bool>,
+    /// Why scoring failed for each frame (null where it didn't); such a frame's entry
+    /// in `confidence_scores` is a placeholder 0.0
+    #[serde(default)]
+    pub scoring_errors: Vec<Option<String>>,
+    /// The smoothed score each frame's accept/reject decision used (null where the
+    /// raw score decided)
+    #[serde(default)]
+    pub smoothed_scores: Vec<Option<f32>>,
+    /// Set when saving was interrupted; the per-frame lists then cover only the frames
+    /// written before it stopped
+    #[serde(default)]
+    pub incomplete: bool,
 }
 
 impl From<&GenerationResult> for OutputMetadata {
@@ -212,27 +1217,185 @@ impl From<&GenerationResult> for OutputMetadata {
             confidence_scores: result.frames.iter().map(|f| f.score).collect(),
             auto_accept: result.frames.iter().map(|f| f.auto_accept).collect(),
             auto_accept_threshold: result.metadata.auto_accept_threshold,
+            seed: result.metadata.seed,
+            near_duplicate: result.frames.iter().map(|f| f.near_duplicate).collect(),
+            style_strength: result.metadata.style_strength,
+            model_version: result.metadata.model_version.clone(),
+            positions: result.metadata.easing.positions(result.frames.len()),
+            repeats_previous: result.frames.iter().map(|f| f.repeats_previous).collect(),
+            auto_reject: result.frames.iter().map(|f| f.auto_reject).collect(),
+            aspect_distorted: result.frames.iter().map(|f| f.aspect_distorted).collect(),
+            synthesized: result.frames.iter().map(|f| f.synthesized).collect(),
+            scoring_errors: result
+                .frames
+                .iter()
+                .map(|f| f.scoring_error.clone())
+                .collect(),
+            smoothed_scores: result.frames.iter().map(|f| f.smoothed_score).collect(),
+            incomplete: false,
         }
     }
 }
 
+impl OutputMetadata {
+    /// Keep the first `frames` frames' entries and mark the run incomplete
+    pub fn truncate(&mut self, frames: usize) {
+        self.confidence_scores.truncate(frames);
+        self.auto_accept.truncate(frames);
+        self.near_duplicate.truncate(frames);
+        self.positions.truncate(frames);
+        self.repeats_previous.truncate(frames);
+        self.auto_reject.truncate(frames);
+        self.aspect_distorted.truncate(frames);
+        self.synthesized.truncate(frames);
+        self.scoring_errors.truncate(frames);
+        self.smoothed_scores.truncate(frames);
+        self.incomplete = true;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{capture_warnings, http_response, serve, warned};
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    /// Generator on the local backend, logging and caching into `dir`
+    fn local_generator(endpoint: &str, dir: &Path) -> Generator {
+        Generator::new(local_config(endpoint, dir)).unwrap()
+    }
+
+    fn local_config(endpoint: &str, dir: &Path) -> Config {
+        let mut config = Config::default();
+        config.api.backend = "local".to_string();
+        config.api.endpoint = endpoint.to_string();
+        config.preprocessing.target_resolution = 16;
+        config.feedback_log_path = Some(dir.join("feedback.jsonl").display().to_string());
+        config.cache_dir = Some(dir.join("cache").display().to_string());
+        config
+    }
+
+    fn write_keyframes(dir: &Path) -> (std::path::PathBuf, std::path::PathBuf) {
+        let a = dir.join("a.png");
+        let b = dir.join("b.png");
+        DynamicImage::new_rgba8(16, 16).save(&a).unwrap();
+        image::RgbaImage::from_pixel(16, 16, image::Rgba([0, 0, 0, 255]))
+            .save(&b)
+            .unwrap();
+        (a, b)
+    }
+
+    #[test]
+    fn test_identical_request_served_from_cache() {
+        let dir = tempdir().unwrap();
+        let (a, b) = write_keyframes(dir.path());
+
+        let frame = image::RgbaImage::from_pixel(16, 16, image::Rgba([128, 128, 128, 255]));
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(frame)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+        let body = serde_json::json!({ "frames": [base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            &png
+        )] });
+
+        // The server answers once, then stops listening
+        let endpoint = serve(vec![http_response(
+            "200 OK",
+            "application/json",
+            body.to_string().as_bytes(),
+        )]);
+        let mut generator = local_generator(&endpoint, dir.path());
+
+        let first = generator
+            .generate_inbetweens(&a, &b, 1, None, None, None)
+            .unwrap();
+        let second = generator
+            .generate_inbetweens(&a, &b, 1, None, None, None)
+            .unwrap();
+        assert_eq!(first.frames.len(), 1);
+        assert_eq!(second.frames.len(), 1);
+
+        // A different seed is a different request, and the server is gone
+        generator.set_seed(Some(7));
+        assert!(generator
+            .generate_inbetweens(&a, &b, 1, None, None, None)
+            .is_err());
+
+        let metrics = generator.metrics();
+        assert_eq!(metrics.generations(), 2);
+        assert_eq!(metrics.api_errors(), 1);
+    }
+
+    #[test]
+    fn test_cache_write_failure_keeps_frames() {
+        let dir = tempdir().unwrap();
+        let (a, b) = write_keyframes(dir.path());
+        let mut png = Vec::new();
+        DynamicImage::new_rgba8(16, 16)
+            .write_to(
+                &mut std::io::Cursor::new(&mut png),
+                image::ImageOutputFormat::Png,
+            )
+            .unwrap();
+        let body = serde_json::json!({ "frames": [base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            &png
+        )] });
+        let endpoint = serve(vec![http_response(
+            "200 OK",
+            "application/json",
+            body.to_string().as_bytes(),
+        )]);
+        let generator = local_generator(&endpoint, dir.path());
+        // The cache directory turns into a file once the generator has made it
+        let cache = dir.path().join("cache");
+        std::fs::remove_dir_all(&cache).unwrap();
+        std::fs::write(&cache, "").unwrap();
+
+        capture_warnings();
+        let result = generator
+            .generate_inbetweens(&a, &b, 1, None, None, None)
+            .unwrap();
+        assert_eq!(result.frames.len(), 1);
+        assert!(warned("Could not cache frames"));
+    }
 
     #[test]
     fn test_output_metadata_conversion() {
         let result = GenerationResult {
+            timings: GenerationTimings::default(),
             frames: vec![
                 ScoredFrame {
                     frame: DynamicImage::new_rgba8(10, 10),
                     score: 0.9,
                     auto_accept: true,
+                    auto_reject: false,
+                    penalties: Vec::new(),
+                    near_duplicate: false,
+                    repeats_previous: false,
+                    aspect_distorted: false,
+                    scoring_error: None,
+                    smoothed_score: None,
+                    synthesized: false,
                 },
                 ScoredFrame {
                     frame: DynamicImage::new_rgba8(10, 10),
                     score: 0.7,
                     auto_accept: false,
+                    auto_reject: false,
+                    penalties: vec![("motion_complexity".to_string(), 0.15)],
+                    near_duplicate: true,
+                    repeats_previous: false,
+                    aspect_distorted: false,
+                    scoring_error: None,
+                    smoothed_score: Some(0.75),
+                    synthesized: false,
                 },
             ],
             metadata: GenerationMetadata {
@@ -241,11 +1404,682 @@ mod tests {
                 auto_accept_threshold: 0.85,
                 original_width: 800,
                 original_height: 600,
+                seed: Some(42),
+                style_strength: Some(0.6),
+                model_version: Some("owner/model:abc".to_string()),
+                easing: Easing::EaseIn,
+                frame_count_note: None,
             },
         };
 
         let output: OutputMetadata = (&result).into();
         assert_eq!(output.confidence_scores.len(), 2);
         assert_eq!(output.auto_accept, vec![true, false]);
+        assert_eq!(output.seed, Some(42));
+        assert_eq!(output.style_strength, Some(0.6));
+        assert_eq!(output.model_version.as_deref(), Some("owner/model:abc"));
+        assert_eq!(output.near_duplicate, vec![false, true]);
+        assert_eq!(output.smoothed_scores, vec![None, Some(0.75)]);
+        assert_eq!(output.positions, Easing::EaseIn.positions(2));
+        assert!((result.sequence_score() - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rescore_frames_from_disk() {
+        let dir = tempdir().unwrap();
+        let (a, b) = write_keyframes(dir.path());
+
+        // A hand-edited midpoint, and a frame that is just keyframe A again
+        let frames_dir = dir.path().join("frames");
+        std::fs::create_dir_all(&frames_dir).unwrap();
+        image::RgbaImage::from_fn(16, 16, |x, _| {
+            let v = 255 - x as u8 * 16;
+            image::Rgba([v, v, v, 255])
+        })
+        .save(frames_dir.join("0000.png"))
+        .unwrap();
+        std::fs::copy(&a, frames_dir.join("0001.png")).unwrap();
+
+        // Nothing listening: rescoring must not touch the API
+        let generator = local_generator("http://127.0.0.1:9", dir.path());
+        let paths = vec![frames_dir.join("0000.png"), frames_dir.join("0001.png")];
+        let result = generator
+            .rescore_frames(&paths, &a, &b, Some("hero"), Some("walk"))
+            .unwrap();
+
+        assert_eq!(result.frames.len(), 2);
+        assert!(result.frames.iter().all(|f| (0.0..=1.0).contains(&f.score)));
+        assert!(!result.frames[0].near_duplicate);
+        assert!(result.frames[1].near_duplicate);
+        assert!(!result.frames[1].auto_accept);
+        assert_eq!(result.metadata.motion_type.as_deref(), Some("walk"));
+        assert_eq!(generator.metrics().frames(), 2);
+    }
+
+    #[test]
+    fn test_scorer_reads_configured_feedback_log() {
+        let dir = tempdir().unwrap();
+        let (a, b) = write_keyframes(dir.path());
+        let log = FeedbackLogger::with_path(dir.path().join("feedback.jsonl")).unwrap();
+        for frame in 0..4 {
+            log.log_rejection(frame, "villain", "walk", &[], None, None)
+                .unwrap();
+        }
+
+        let generator = local_generator("http://127.0.0.1:9", dir.path());
+        let result = generator
+            .rescore_frames(
+                std::slice::from_ref(&a),
+                &a,
+                &b,
+                Some("villain"),
+                Some("walk"),
+            )
+            .unwrap();
+        let historical = result.frames[0]
+            .penalties
+            .iter()
+            .find(|(name, _)| name == "historical_success")
+            .map(|(_, penalty)| *penalty);
+        assert_eq!(historical, Some(0.35));
+    }
+
+    #[test]
+    fn test_scoring_error_keeps_other_frames() {
+        struct FailsOnRed;
+
+        impl Heuristic for FailsOnRed {
+            fn name(&self) -> &'static str {
+                "fails_on_red"
+            }
+
+            fn penalty(&self, _ctx: &ScoringContext) -> f32 {
+                0.0
+            }
+
+            fn try_penalty(&self, ctx: &ScoringContext) -> Result<f32> {
+                if ctx.generated.to_rgba8().get_pixel(8, 8).0 == [255, 0, 0, 255] {
+                    anyhow::bail!("cannot handle red");
+                }
+                Ok(0.0)
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let (a, b) = write_keyframes(dir.path());
+        let paths: Vec<_> = [[128, 128, 128, 255], [255, 0, 0, 255], [64, 64, 64, 255]]
+            .iter()
+            .enumerate()
+            .map(|(i, color)| {
+                let path = dir.path().join(format!("{i:04}.png"));
+                image::RgbaImage::from_pixel(16, 16, image::Rgba(*color))
+                    .save(&path)
+                    .unwrap();
+                path
+            })
+            .collect();
+
+        let mut generator = local_generator("http://127.0.0.1:9", dir.path());
+        generator.add_heuristic(FailsOnRed);
+        let result = generator
+            .rescore_frames(&paths, &a, &b, None, None)
+            .unwrap();
+
+        assert_eq!(result.frames.len(), 3);
+        let failed = &result.frames[1];
+        assert!(failed
+            .scoring_error
+            .as_deref()
+            .unwrap()
+            .contains("cannot handle red"));
+        assert!(failed.score.abs() < f32::EPSILON);
+        assert!(!failed.auto_accept);
+        assert!(result.frames[0].scoring_error.is_none());
+        assert!(result.frames[2].scoring_error.is_none());
+        assert!(result.frames[2].score > 0.0);
+
+        // The placeholder 0.0 neither drags down the mean nor passes for a real score
+        let scored = (result.frames[0].score + result.frames[2].score) / 2.0;
+        assert!((result.sequence_score() - scored).abs() < 1e-6);
+        let metadata = OutputMetadata::from(&result);
+        assert!(metadata.scoring_errors[1]
+            .as_deref()
+            .unwrap()
+            .contains("cannot handle red"));
+        assert_eq!(metadata.scoring_errors[0], None);
+    }
+
+    /// Generator on the offline crossfade backend, logging into `dir`
+    fn crossfade_generator(dir: &Path) -> Generator {
+        let mut config = Config::default();
+        config.api.backend = "crossfade".to_string();
+        config.preprocessing.target_resolution = 16;
+        config.feedback_log_path = Some(dir.join("feedback.jsonl").display().to_string());
+        Generator::new(config).unwrap()
+    }
+
+    #[test]
+    fn test_mismatched_keyframes_warn_and_generate() {
+        capture_warnings();
+        let dir = tempdir().unwrap();
+        let generator = crossfade_generator(dir.path());
+
+        // Same 4:3 drawing at two sizes
+        let draw = |width: u32, height: u32| {
+            DynamicImage::ImageRgba8(image::RgbaImage::from_fn(width, height, |x, _| {
+                let alpha = if x < width / 2 { 255 } else { 0 };
+                image::Rgba([0, 0, 0, alpha])
+            }))
+        };
+        let result = generator
+            .generate_inbetweens_from_images(&draw(8, 6), &draw(16, 12), 3, None, None, None)
+            .unwrap();
+
+        assert!(warned("Keyframes differ in size (8x6 vs 16x12)"));
+        assert_eq!(result.frames.len(), 3);
+        assert_eq!(result.frames[1].frame.dimensions(), (8, 6));
+        assert_eq!(result.metadata.motion_type.as_deref(), Some("static"));
+    }
+
+    #[test]
+    fn test_identical_keyframes_warn() {
+        capture_warnings();
+        let dir = tempdir().unwrap();
+        let generator = crossfade_generator(dir.path());
+
+        let pose = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(16, 16, |x, y| {
+            image::Rgba([0, 0, 0, if x == y { 255 } else { 0 }])
+        }));
+        let result = generator
+            .generate_inbetweens_from_images(&pose, &pose.clone(), 2, None, None, None)
+            .unwrap();
+
+        assert!(warned("Keyframes are nearly identical"));
+        assert_eq!(result.frames.len(), 2);
+    }
+
+    #[test]
+    fn test_repeated_frames_dropped_or_flagged() {
+        let dir = tempdir().unwrap();
+        let (a, b) = write_keyframes(dir.path());
+
+        // A vertical stroke at column `x`, as a base64 PNG
+        let stroke = |x: u32| {
+            let frame = image::RgbaImage::from_fn(16, 16, |px, _| {
+                let value = if (x..x + 3).contains(&px) { 0 } else { 255 };
+                image::Rgba([value, value, value, 255])
+            });
+            let mut png = Vec::new();
+            DynamicImage::ImageRgba8(frame)
+                .write_to(
+                    &mut std::io::Cursor::new(&mut png),
+                    image::ImageOutputFormat::Png,
+                )
+                .unwrap();
+            base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &png)
+        };
+        // The backend padded two poses out to four frames
+        let body = serde_json::json!({ "frames": [stroke(3), stroke(3), stroke(10), stroke(10)] });
+        let endpoint = serve(vec![http_response(
+            "200 OK",
+            "application/json",
+            body.to_string().as_bytes(),
+        )]);
+        let generator = |drop: bool| {
+            let mut config = local_config(&endpoint, dir.path());
+            config.repeated_frame_distance = Some(2);
+            config.drop_repeated_frames = drop;
+            Generator::new(config).unwrap()
+        };
+
+        let flagged = generator(false)
+            .generate_inbetweens(&a, &b, 4, None, None, None)
+            .unwrap();
+        let repeats: Vec<_> = flagged.frames.iter().map(|f| f.repeats_previous).collect();
+        assert_eq!(repeats, vec![false, true, false, true]);
+        assert!(!flagged.frames[3].auto_accept);
+
+        // Same request again, served from the cache
+        let dropped = generator(true)
+            .generate_inbetweens(&a, &b, 4, None, None, None)
+            .unwrap();
+        assert_eq!(dropped.frames.len(), 2);
+        let metadata = OutputMetadata::from(&dropped);
+        assert_eq!(metadata.positions, vec![0.2, 0.6]);
+        assert_eq!(metadata.repeats_previous, vec![false, false]);
+    }
+
+    #[test]
+    fn test_num_frames_bounds() {
+        let dir = tempdir().unwrap();
+        let (a, b) = write_keyframes(dir.path());
+        let generator = crossfade_generator(dir.path());
+
+        let err = generator
+            .generate_inbetweens(&a, &b, 0, None, None, None)
+            .unwrap_err();
+        assert!(err.to_string().contains("at least 1"));
+
+        let err = generator
+            .generate_inbetweens(&a, &b, 10_000, None, None, None)
+            .unwrap_err();
+        assert!(err.to_string().contains("limit of 64"));
+
+        // Nothing was generated or logged for the refused requests
+        assert_eq!(generator.metrics().generations(), 0);
+        assert_eq!(
+            generator
+                .generate_inbetweens(&a, &b, 64, None, None, None)
+                .unwrap()
+                .frames
+                .len(),
+            64
+        );
+    }
+
+    #[test]
+    fn test_crossfade_generation_timings() {
+        let dir = tempdir().unwrap();
+        let (a, b) = write_keyframes(dir.path());
+        let generator = crossfade_generator(dir.path());
+
+        let timings = generator
+            .generate_inbetweens(&a, &b, 3, None, None, None)
+            .unwrap()
+            .timings;
+        let stages = [
+            timings.preprocess_ms,
+            timings.api_ms,
+            timings.download_ms,
+            timings.score_ms,
+        ];
+        assert!(stages.iter().all(Option::is_some));
+        let total = timings.total_ms.unwrap();
+        assert!(stages.iter().flatten().sum::<u64>() <= total);
+    }
+
+    #[test]
+    fn test_reject_threshold_bands() {
+        let dir = tempdir().unwrap();
+        let mut config = local_config("http://127.0.0.1:1", dir.path());
+        config.reject_threshold = Some(0.3);
+        let generator = Generator::new(config).unwrap();
+        let frame = |score: f32, error: Option<&str>| {
+            let score = FrameScore {
+                breakdown: ScoreBreakdown {
+                    score,
+                    penalties: Vec::new(),
+                },
+                near_duplicate: false,
+                aspect_distorted: false,
+                error: error.map(String::from),
+                smoothed: None,
+            };
+            let frame = generator.scored_frame(DynamicImage::new_rgba8(2, 2), score, false);
+            (frame.auto_accept, frame.auto_reject)
+        };
+
+        assert_eq!(frame(0.9, None), (true, false));
+        assert_eq!(frame(0.5, None), (false, false));
+        assert_eq!(frame(0.1, None), (false, true));
+        // Failed scoring is left for review, not rejected
+        assert_eq!(frame(0.0, Some("corrupt")), (false, false));
+    }
+
+    #[test]
+    fn test_stretched_frame_flagged() {
+        /// Returns a frame shaped like the keyframes, then one stretched to a square
+        struct Stretching;
+
+        impl Backend for Stretching {
+            fn name(&self) -> &'static str {
+                "stretching"
+            }
+
+            fn generate(
+                &self,
+                frame_a: &DynamicImage,
+                _frame_b: &DynamicImage,
+                _num_frames: u32,
+                _request: &BackendRequest,
+            ) -> Result<Vec<DynamicImage>> {
+                let (width, height) = frame_a.dimensions();
+                Ok(vec![
+                    silhouette(width, height, 6..10),
+                    silhouette(width, height, 2..14),
+                ])
+            }
+        }
+
+        /// Opaque block over rows 2..14 and the given columns, on transparency
+        fn silhouette(width: u32, height: u32, columns: std::ops::Range<u32>) -> DynamicImage {
+            DynamicImage::ImageRgba8(image::RgbaImage::from_fn(width, height, |x, y| {
+                let alpha = if columns.contains(&x) && (2..14).contains(&y) {
+                    255
+                } else {
+                    0
+                };
+                image::Rgba([0, 0, 0, alpha])
+            }))
+        }
+
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.api.backend = "crossfade".to_string();
+        config.preprocessing.target_resolution = 16;
+        config.feedback_log_path = Some(dir.path().join("feedback.jsonl").display().to_string());
+        config.aspect_tolerance = Some(0.25);
+        let mut generator = Generator::new(config).unwrap();
+        generator.set_backend(Stretching);
+
+        let result = generator
+            .generate_inbetweens_from_images(
+                &silhouette(16, 16, 3..7),
+                &silhouette(16, 16, 9..13),
+                2,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let flagged: Vec<_> = result.frames.iter().map(|f| f.aspect_distorted).collect();
+        assert_eq!(flagged, vec![false, true]);
+        assert!(!result.frames[1].auto_accept);
+        assert_eq!(
+            OutputMetadata::from(&result).aspect_distorted,
+            vec![false, true]
+        );
+    }
+
+    #[test]
+    fn test_short_native_output_noted() {
+        /// Only ever produces two frames, however many are asked for
+        struct ShortVideo;
+
+        impl Backend for ShortVideo {
+            fn name(&self) -> &'static str {
+                "short-video"
+            }
+
+            fn generate(
+                &self,
+                frame_a: &DynamicImage,
+                frame_b: &DynamicImage,
+                _num_frames: u32,
+                _request: &BackendRequest,
+            ) -> Result<Vec<DynamicImage>> {
+                Ok(vec![frame_a.clone(), frame_b.clone()])
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let mut config = Config::default();
+        config.api.backend = "crossfade".to_string();
+        config.preprocessing.target_resolution = 16;
+        config.feedback_log_path = Some(dir.path().join("feedback.jsonl").display().to_string());
+        let mut generator = Generator::new(config).unwrap();
+        let keyframe = |value: u8| {
+            DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+                16,
+                16,
+                image::Rgba([value, 0, 0, 255]),
+            ))
+        };
+
+        let full = generator
+            .generate_inbetweens_from_images(&keyframe(0), &keyframe(200), 2, None, None, None)
+            .unwrap();
+        assert_eq!(full.metadata.frame_count_note, None);
+
+        generator.set_backend(ShortVideo);
+        let short = generator
+            .generate_inbetweens_from_images(&keyframe(0), &keyframe(200), 5, None, None, None)
+            .unwrap();
+        assert_eq!(short.frames.len(), 2);
+        assert_eq!(
+            short.metadata.frame_count_note,
+            Some(FrameCountNote {
+                requested: 5,
+                delivered: 2,
+                reason: "model produced only 2 native frames".to_string(),
+            })
+        );
+
+        // Upsampled to the requested count, keeping the native frame that lands on a
+        // requested position (1/3 and 2/3 of 5 slots: only 0.5 is off a native frame)
+        let mut config = Config::default();
+        config.api.backend = "crossfade".to_string();
+        config.preprocessing.target_resolution = 16;
+        config.feedback_log_path = Some(dir.path().join("feedback.jsonl").display().to_string());
+        config.upsample_when_short = true;
+        let mut generator = Generator::new(config).unwrap();
+        generator.set_backend(ShortVideo);
+        let upsampled = generator
+            .generate_inbetweens_from_images(&keyframe(0), &keyframe(200), 5, None, None, None)
+            .unwrap();
+        assert_eq!(upsampled.frames.len(), 5);
+        assert_eq!(upsampled.metadata.frame_count_note, None);
+        let synthesized: Vec<bool> = upsampled.frames.iter().map(|f| f.synthesized).collect();
+        assert_eq!(synthesized, [true, false, true, false, true]);
+        assert_eq!(
+            OutputMetadata::from(&upsampled).synthesized,
+            [true, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_low_confidence_sequence_retried() {
+        /// Blank frames on the first call, then the block moved partway; records each
+        /// call's seed
+        struct FailsOnce(Arc<Mutex<Vec<Option<i64>>>>);
+
+        impl Backend for FailsOnce {
+            fn name(&self) -> &'static str {
+                "fails-once"
+            }
+
+            fn generate(
+                &self,
+                frame_a: &DynamicImage,
+                _frame_b: &DynamicImage,
+                num_frames: u32,
+                request: &BackendRequest,
+            ) -> Result<Vec<DynamicImage>> {
+                let mut seeds = self.0.lock().unwrap();
+                seeds.push(request.seed);
+                let (width, height) = frame_a.dimensions();
+                if seeds.len() == 1 {
+                    return Ok(vec![
+                        DynamicImage::new_rgba8(width, height);
+                        num_frames as usize
+                    ]);
+                }
+                Ok((0..num_frames)
+                    .map(|i| block(width, height, 5 + i))
+                    .collect())
+            }
+        }
+
+        /// Dark block over rows 8..24 and 8 columns from `left`, on transparency
+        fn block(width: u32, height: u32, left: u32) -> DynamicImage {
+            DynamicImage::ImageRgba8(image::RgbaImage::from_fn(width, height, |x, y| {
+                let inside = (left..left + 8).contains(&x) && (8..24).contains(&y);
+                image::Rgba([20, 20, 20, if inside { 255 } else { 0 }])
+            }))
+        }
+
+        let dir = tempdir().unwrap();
+        let generate = |retry: RetryOnLowConfidence, seed: Option<i64>| {
+            let mut config = Config::default();
+            config.api.backend = "crossfade".to_string();
+            config.preprocessing.target_resolution = 32;
+            config.feedback_log_path =
+                Some(dir.path().join("feedback.jsonl").display().to_string());
+            config.retry_on_low_confidence = retry;
+            let seeds = Arc::new(Mutex::new(Vec::new()));
+            let mut generator = Generator::new(config).unwrap();
+            generator.set_backend(FailsOnce(Arc::clone(&seeds)));
+            generator.set_seed(seed);
+            let result = generator
+                .generate_inbetweens_from_images(
+                    &block(32, 32, 4),
+                    &block(32, 32, 8),
+                    3,
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            let seeds = seeds.lock().unwrap().clone();
+            (result, seeds, generator.metrics().frames())
+        };
+
+        let (garbage, seeds, _) = generate(RetryOnLowConfidence::default(), None);
+        assert_eq!(seeds, vec![None]);
+        assert!(garbage.sequence_score() < 0.3);
+
+        let retry = RetryOnLowConfidence {
+            enabled: true,
+            mean_threshold: 0.3,
+            max_retries: 2,
+        };
+        let (good, seeds, frames) = generate(retry.clone(), None);
+        // The good second attempt ends the retries
+        assert_eq!(seeds.len(), 2);
+        assert_eq!(seeds[0], None);
+        assert!(seeds[1].is_some());
+        assert_eq!(good.metadata.seed, seeds[1]);
+        assert!(good.sequence_score() >= 0.3);
+        // Only the kept attempt's frames count as generated
+        assert_eq!(frames, 3);
+
+        // A seed the user fixed is kept, however the frames score
+        let (fixed, seeds, _) = generate(retry, Some(5));
+        assert_eq!(seeds, vec![Some(5)]);
+        assert_eq!(fixed.metadata.seed, Some(5));
+    }
+
+    #[test]
+    fn test_cancel_keeps_returned_frames_and_skips_retries() {
+        /// Blank frames, as if Ctrl-C was pressed while the backend worked
+        struct CanceledDuring(Arc<Mutex<u32>>);
+
+        impl Backend for CanceledDuring {
+            fn name(&self) -> &'static str {
+                "canceled-during"
+            }
+
+            fn generate(
+                &self,
+                frame_a: &DynamicImage,
+                _frame_b: &DynamicImage,
+                num_frames: u32,
+                request: &BackendRequest,
+            ) -> Result<Vec<DynamicImage>> {
+                *self.0.lock().unwrap() += 1;
+                request
+                    .cancel
+                    .store(true, std::sync::atomic::Ordering::SeqCst);
+                let (width, height) = frame_a.dimensions();
+                Ok(vec![
+                    DynamicImage::new_rgba8(width, height);
+                    num_frames as usize
+                ])
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        let mut config = local_config("http://127.0.0.1:1", dir.path());
+        config.preprocessing.target_resolution = 32;
+        config.retry_on_low_confidence = RetryOnLowConfidence {
+            enabled: true,
+            mean_threshold: 0.99,
+            max_retries: 3,
+        };
+        let calls = Arc::new(Mutex::new(0));
+        let mut generator = Generator::new(config).unwrap();
+        generator.set_backend(CanceledDuring(Arc::clone(&calls)));
+        let frame = DynamicImage::new_rgba8(32, 32);
+        let result = generator
+            .generate_inbetweens_from_images(&frame, &frame, 3, None, None, None)
+            .unwrap();
+        assert_eq!(result.frames.len(), 3);
+        assert_eq!(*calls.lock().unwrap(), 1);
+
+        // Nothing new starts once canceled (a cached pair would still be free to load)
+        let other = DynamicImage::new_rgb8(32, 32);
+        let err = generator
+            .generate_inbetweens_from_images(&other, &other, 3, None, None, None)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<api::ApiError>(),
+            Some(api::ApiError::Cancelled)
+        ));
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_smoothing_lifts_isolated_dip() {
+        let dir = tempdir().unwrap();
+        let mut config = local_config("http://127.0.0.1:1", dir.path());
+        config.confidence.smooth_scores = true;
+        config.confidence.smoothing_window = 3;
+        let generator = Generator::new(config).unwrap();
+        let raw = [0.95, 0.95, 0.7, 0.95, 0.95];
+        let mut scores: Vec<FrameScore> = raw
+            .iter()
+            .map(|&score| FrameScore {
+                breakdown: ScoreBreakdown {
+                    score,
+                    penalties: Vec::new(),
+                },
+                near_duplicate: false,
+                aspect_distorted: false,
+                error: None,
+                smoothed: None,
+            })
+            .collect();
+
+        generator.smooth_scores(&mut scores);
+        let frames: Vec<ScoredFrame> = scores
+            .into_iter()
+            .map(|score| generator.scored_frame(DynamicImage::new_rgba8(2, 2), score, false))
+            .collect();
+
+        // (0.95 + 0.7 + 0.95) / 3 clears the 0.85 threshold; the raw score is kept
+        assert!(frames.iter().all(|frame| frame.auto_accept));
+        assert!((frames[2].score - 0.7).abs() < 1e-6);
+
+        // The score that decided is recorded next to the raw one
+        assert!((frames[2].smoothed_score.unwrap() - 2.6 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_unreadable_keyframes_are_named() {
+        let dir = tempdir().unwrap();
+        let empty = dir.path().join("empty.png");
+        std::fs::write(&empty, b"").unwrap();
+        let garbage = dir.path().join("garbage.png");
+        std::fs::write(&garbage, b"\x89PNG\r\n\x1a\nnot really").unwrap();
+
+        let message = format!("{:#}", load_keyframe(&empty).unwrap_err());
+        assert!(
+            message.contains("empty.png is empty (0 bytes)"),
+            "{message}"
+        );
+
+        let message = format!("{:#}", load_keyframe(&garbage).unwrap_err());
+        assert!(
+            message.contains("garbage.png could not be decoded"),
+            "{message}"
+        );
+
+        // Generation fails the same way before touching the backend
+        let generator = local_generator("http://127.0.0.1:9", dir.path());
+        let err = generator
+            .generate_inbetweens(&empty, &garbage, 1, None, None, None)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("empty.png is empty"));
     }
 }
@@ -0,0 +1,148 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Confidence scores are accumulated as integer millionths so they fit an atomic
+const CONFIDENCE_SCALE: f64 = 1_000_000.0;
+
+/// Counters updated during generation, renderable in Prometheus text format
+#[derive(Debug, Default)]
+pub struct Metrics {
+    generations: AtomicU64,
+    frames: AtomicU64,
+    api_errors: AtomicU64,
+    cache_hits: AtomicU64,
+    near_duplicates: AtomicU64,
+    confidence_micros: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_generation(&self) {
+        self.generations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_frame(&self, confidence: f32, near_duplicate: bool) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        let micros = (f64::from(confidence.clamp(0.0, 1.0)) * CONFIDENCE_SCALE).round() as u64;
+        self.confidence_micros.fetch_add(micros, Ordering::Relaxed);
+        if near_duplicate {
+            self.near_duplicates.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_api_error(&self) {
+        self.api_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn generations(&self) -> u64 {
+        self.generations.load(Ordering::Relaxed)
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+
+    pub fn api_errors(&self) -> u64 {
+        self.api_errors.load(Ordering::Relaxed)
+    }
+
+    /// Mean confidence over all scored frames (0.0 before any frame)
+    pub fn average_confidence(&self) -> f64 {
+        let frames = self.frames();
+        if frames == 0 {
+            return 0.0;
+        }
+        self.confidence_sum() / frames as f64
+    }
+
+    fn confidence_sum(&self) -> f64 {
+        self.confidence_micros.load(Ordering::Relaxed) as f64 / CONFIDENCE_SCALE
+    }
+
+    /// Render all counters in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: &dyn std::fmt::Display| {
+            let _ = writeln!(out, "# HELP gp_inbetween_{name} {help}");
+            let _ = writeln!(out, "# TYPE gp_inbetween_{name} {kind}");
+            let _ = writeln!(out, "gp_inbetween_{name} {value}");
+        };
+
+        metric(
+            "generations_total",
+            "counter",
+            "Completed inbetween generations",
+            &self.generations(),
+        );
+        metric(
+            "frames_total",
+            "counter",
+            "Generated frames scored",
+            &self.frames(),
+        );
+        metric(
+            "api_errors_total",
+            "counter",
+            "Failed API calls",
+            &self.api_errors(),
+        );
+        metric(
+            "cache_hits_total",
+            "counter",
+            "Generations served from the cache",
+            &self.cache_hits.load(Ordering::Relaxed),
+        );
+        metric(
+            "near_duplicate_frames_total",
+            "counter",
+            "Frames flagged as copies of a keyframe",
+            &self.near_duplicates.load(Ordering::Relaxed),
+        );
+        metric(
+            "frame_confidence_sum",
+            "counter",
+            "Sum of frame confidence scores",
+            &self.confidence_sum(),
+        );
+        metric(
+            "frame_confidence_average",
+            "gauge",
+            "Mean frame confidence score",
+            &self.average_confidence(),
+        );
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus() {
+        let metrics = Metrics::new();
+        metrics.record_generation();
+        metrics.record_frame(0.9, false);
+        metrics.record_frame(0.5, true);
+        metrics.record_api_error();
+
+        assert_eq!(metrics.frames(), 2);
+        assert!((metrics.average_confidence() - 0.7).abs() < 1e-6);
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains("# TYPE gp_inbetween_generations_total counter\n"));
+        assert!(text.contains("gp_inbetween_generations_total 1\n"));
+        assert!(text.contains("gp_inbetween_frames_total 2\n"));
+        assert!(text.contains("gp_inbetween_api_errors_total 1\n"));
+        assert!(text.contains("gp_inbetween_near_duplicate_frames_total 1\n"));
+        assert!(text.contains("gp_inbetween_frame_confidence_average 0.7\n"));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{GenerationResult, OutputMetadata, ScoredFrame};
+use anyhow::Result;
+use image::DynamicImage;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Where a finished generation goes. The CLI writes a directory of image files plus
+/// metadata.json; embedders can keep frames in memory or hand them straight to their
+/// own renderer instead.
+pub trait OutputWriter {
+    /// Called once per frame, in sequence order
+    fn write_frame(&mut self, index: usize, frame: &ScoredFrame) -> Result<()>;
+
+    /// Called once after every frame has been written
+    fn write_metadata(&mut self, metadata: &OutputMetadata) -> Result<()>;
+}
+
+/// Collects the frames in memory, ignoring the metadata
+impl OutputWriter for Vec<DynamicImage> {
+    fn write_frame(&mut self, _index: usize, frame: &ScoredFrame) -> Result<()> {
+        self.push(frame.frame.clone());
+        Ok(())
+    }
+
+    fn write_metadata(&mut self, _metadata: &OutputMetadata) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl GenerationResult {
+    /// Hand every frame and then the metadata to `writer`
+    pub fn write_to(&self, writer: &mut dyn OutputWriter) -> Result<()> {
+        self.write_until(writer, &AtomicBool::new(false))
+            .map(|_| ())
+    }
+
+    /// [`Self::write_to`], stopping before the next frame once `interrupted` is set.
+    /// The metadata then covers the frames already written and is marked incomplete.
+    /// Returns how many frames were written.
+    pub fn write_until(
+        &self,
+        writer: &mut dyn OutputWriter,
+        interrupted: &AtomicBool,
+    ) -> Result<usize> {
+        let mut metadata = OutputMetadata::from(self);
+        for (index, frame) in self.frames.iter().enumerate() {
+            if interrupted.load(Ordering::SeqCst) {
+                metadata.truncate(index);
+                writer.write_metadata(&metadata)?;
+                return Ok(index);
+            }
+            writer.write_frame(index, frame)?;
+        }
+        writer.write_metadata(&metadata)?;
+        Ok(self.frames.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Easing, GenerationMetadata, GenerationTimings};
+    use std::sync::Arc;
+
+    /// Keeps everything it is given, the way an embedder's sink would
+    #[derive(Default)]
+    struct MemoryWriter {
+        frames: Vec<(usize, f32, DynamicImage)>,
+        metadata: Option<OutputMetadata>,
+        /// Set after the first frame, like a Ctrl-C arriving mid-save
+        interrupt: Option<Arc<AtomicBool>>,
+    }
+
+    impl OutputWriter for MemoryWriter {
+        fn write_frame(&mut self, index: usize, frame: &ScoredFrame) -> Result<()> {
+            self.frames.push((index, frame.score, frame.frame.clone()));
+            if let Some(interrupt) = &self.interrupt {
+                interrupt.store(true, Ordering::SeqCst);
+            }
+            Ok(())
+        }
+
+        fn write_metadata(&mut self, metadata: &OutputMetadata) -> Result<()> {
+            self.metadata = Some(metadata.clone());
+            Ok(())
+        }
+    }
+
+    fn two_frame_result() -> GenerationResult {
+        let frame = |score: f32| ScoredFrame {
+            frame: DynamicImage::new_rgba8(3, 2),
+            score,
+            auto_accept: score >= 0.85,
+            auto_reject: false,
+            penalties: Vec::new(),
+            near_duplicate: false,
+            repeats_previous: false,
+            aspect_distorted: false,
+            scoring_error: None,
+            smoothed_score: None,
+            synthesized: false,
+        };
+        GenerationResult {
+            frames: vec![frame(0.9), frame(0.6)],
+            metadata: GenerationMetadata {
+                character: Some("hero".to_string()),
+                motion_type: Some("walk".to_string()),
+                auto_accept_threshold: 0.85,
+                original_width: 3,
+                original_height: 2,
+                seed: None,
+                style_strength: None,
+                model_version: None,
+                easing: Easing::Linear,
+                frame_count_note: None,
+            },
+            timings: GenerationTimings::default(),
+        }
+    }
+
+    #[test]
+    fn test_in_memory_writer_captures_frames() {
+        let result = two_frame_result();
+        let mut writer = MemoryWriter::default();
+        result.write_to(&mut writer).unwrap();
+        let captured: Vec<_> = writer.frames.iter().map(|(i, s, _)| (*i, *s)).collect();
+        assert_eq!(captured, vec![(0, 0.9), (1, 0.6)]);
+        assert_eq!(writer.frames[1].2.width(), 3);
+        let metadata = writer.metadata.unwrap();
+        assert_eq!(metadata.auto_accept, vec![true, false]);
+        assert!(!metadata.incomplete);
+
+        let mut images: Vec<DynamicImage> = Vec::new();
+        result.write_to(&mut images).unwrap();
+        assert_eq!(images.len(), 2);
+    }
+
+    #[test]
+    fn test_interrupted_save_writes_partial_metadata() {
+        let result = two_frame_result();
+        let interrupted = Arc::new(AtomicBool::new(false));
+        let mut writer = MemoryWriter {
+            interrupt: Some(Arc::clone(&interrupted)),
+            ..MemoryWriter::default()
+        };
+
+        assert_eq!(result.write_until(&mut writer, &interrupted).unwrap(), 1);
+        assert_eq!(writer.frames.len(), 1);
+        // The metadata describes exactly the saved frame and survives a round trip
+        let json = serde_json::to_string(&writer.metadata.unwrap()).unwrap();
+        let metadata: OutputMetadata = serde_json::from_str(&json).unwrap();
+        assert!(metadata.incomplete);
+        assert_eq!(metadata.confidence_scores, vec![0.9]);
+        assert_eq!(metadata.auto_accept, vec![true]);
+        assert_eq!(metadata.positions.len(), 1);
+        assert_eq!(metadata.character.as_deref(), Some("hero"));
+    }
+}
@@ -1,6 +1,13 @@
-use crate::config::PreprocessingConfig;
+use crate::confidence::is_grayscale;
+use crate::config::{BackgroundKey, Connectivity, KeyColor, PreprocessingConfig};
 use anyhow::Result;
-use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba, imageops::FilterType};
+use image::{
+    imageops::FilterType, ColorType, DynamicImage, GenericImageView, ImageBuffer, Rgba, RgbaImage,
+};
+
+/// Rows per cleanup tile: enough work per tile to be worth a thread, while 4K frames
+/// still split into dozens of tiles
+const CLEANUP_TILE_ROWS: usize = 64;
 
 pub struct Preprocessor {
     config: PreprocessingConfig,
@@ -15,7 +22,12 @@ impl Preprocessor {
 
     /// Process an image: normalize resolution and optionally clean up
     pub fn process(&self, img: &DynamicImage) -> Result<DynamicImage> {
-        let mut processed = img.clone();
+        // First, so everything after sees the keyed-out paper as transparency
+        let keyed = match &self.config.remove_background {
+            Some(key) => self.remove_background(img, key),
+            None => img.clone(),
+        };
+        let mut processed = keyed.clone();
 
         // Normalize resolution if enabled
         if self.config.normalize_resolution {
@@ -27,116 +39,197 @@ impl Preprocessor {
             processed = self.cleanup(&processed);
         }
 
+        // Last, so cleanup's alpha binarization doesn't undo it
+        if self.config.normalize_resolution
+            && self.config.feather_radius > 0
+            && !is_grayscale(&keyed)
+        {
+            let (width, height) = img.dimensions();
+            processed = self.feather_seams(&processed, &self.get_padding_info(width, height));
+        }
+
         Ok(processed)
     }
 
+    /// Make pixels matching the paper color transparent, for scans and photos of line
+    /// art that have no alpha of their own
+    pub fn remove_background(&self, img: &DynamicImage, key: &BackgroundKey) -> DynamicImage {
+        let mut rgba = img.to_rgba8();
+        let paper = match key.color {
+            KeyColor::Rgb(color) => color,
+            KeyColor::Auto => corner_color(&rgba),
+        };
+        let tolerance = u32::from(key.tolerance);
+
+        for pixel in rgba.pixels_mut() {
+            let distance = (0..3)
+                .map(|c| u32::from(pixel[c].abs_diff(paper[c])))
+                .max()
+                .unwrap_or(0);
+            let factor = if distance <= tolerance {
+                0.0
+            } else if distance < 2 * tolerance {
+                (distance - tolerance) as f32 / tolerance as f32
+            } else {
+                continue;
+            };
+            pixel[3] = (f32::from(pixel[3]) * factor).round() as u8;
+        }
+
+        DynamicImage::ImageRgba8(rgba)
+    }
+
+    /// Ramp alpha down toward the content edges that border padding. Edges on the
+    /// canvas border have no seam and are left alone.
+    fn feather_seams(&self, img: &DynamicImage, padding: &PaddingInfo) -> DynamicImage {
+        let radius = self.config.feather_radius;
+        let mut rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let (left, top) = (padding.x_offset, padding.y_offset);
+        let (right, bottom) = (left + padding.scaled_width, top + padding.scaled_height);
+
+        for y in top..bottom {
+            for x in left..right {
+                // Distance (0 = outermost content pixel) to the nearest edge with padding
+                let distance = [
+                    (left > 0).then(|| x - left),
+                    (right < width).then(|| right - 1 - x),
+                    (top > 0).then(|| y - top),
+                    (bottom < height).then(|| bottom - 1 - y),
+                ]
+                .into_iter()
+                .flatten()
+                .min();
+
+                if let Some(distance) = distance.filter(|&d| d < radius) {
+                    let pixel = rgba.get_pixel_mut(x, y);
+                    let factor = (distance + 1) as f32 / (radius + 1) as f32;
+                    pixel[3] = (f32::from(pixel[3]) * factor).round() as u8;
+                }
+            }
+        }
+
+        DynamicImage::ImageRgba8(rgba)
+    }
+
     /// Resize and pad image to target square resolution
     fn normalize_resolution(&self, img: &DynamicImage) -> DynamicImage {
         let target = self.config.target_resolution;
-        let (width, height) = img.dimensions();
 
         // Already at target size
-        if width == target && height == target {
+        if img.dimensions() == (target, target) {
             return img.clone();
         }
 
-        // Calculate scale to fit within target while preserving aspect ratio
-        let scale = (target as f32) / (width.max(height) as f32);
-        let new_width = ((width as f32) * scale).round() as u32;
-        let new_height = ((height as f32) * scale).round() as u32;
-
-        log::debug!(
-            "Resizing {}x{} -> {}x{} (target {})",
-            width,
-            height,
-            new_width,
-            new_height,
-            target
-        );
+        letterbox(img, target, target)
+    }
 
-        // Resize with high-quality interpolation
-        let resized = img.resize(new_width, new_height, FilterType::Lanczos3);
+    /// Clean up the image by removing noise and artifacts
+    fn cleanup(&self, img: &DynamicImage) -> DynamicImage {
+        let rgba = img.to_rgba8();
+        let (width, height) = rgba.dimensions();
 
-        // Create transparent canvas at target size
-        let mut canvas: ImageBuffer<Rgba<u8>, Vec<u8>> =
-            ImageBuffer::from_pixel(target, target, Rgba([0, 0, 0, 0]));
+        // Create output buffer
+        let mut output: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
+        self.remove_isolated_pixels(&rgba, &mut output, CLEANUP_TILE_ROWS);
 
-        // Center the resized image on the canvas
-        let x_offset = (target - new_width) / 2;
-        let y_offset = (target - new_height) / 2;
+        if !self.config.binarize_alpha {
+            return DynamicImage::ImageRgba8(output);
+        }
 
-        // Copy resized image onto canvas
-        let resized_rgba = resized.to_rgba8();
-        for (x, y, pixel) in resized_rgba.enumerate_pixels() {
-            let canvas_x = x + x_offset;
-            let canvas_y = y + y_offset;
-            if canvas_x < target && canvas_y < target {
-                canvas.put_pixel(canvas_x, canvas_y, *pixel);
+        // Clean alpha channel: make pixels either fully transparent or fully opaque
+        for pixel in output.pixels_mut() {
+            if pixel[3] < self.config.alpha_threshold {
+                *pixel = Rgba([0, 0, 0, 0]);
+            } else {
+                pixel[3] = 255;
             }
         }
 
-        DynamicImage::ImageRgba8(canvas)
+        DynamicImage::ImageRgba8(output)
     }
 
-    /// Clean up the image by removing noise and artifacts
-    fn cleanup(&self, img: &DynamicImage) -> DynamicImage {
-        let rgba = img.to_rgba8();
-        let (width, height) = rgba.dimensions();
+    /// Copy `rgba` into `output` minus isolated pixels (noise), in tiles of `tile_rows`
+    /// rows. Tiles are independent (each reads the rows around it from `rgba`), so with
+    /// the `parallel` feature they run across threads, with output identical to serial.
+    fn remove_isolated_pixels(&self, rgba: &RgbaImage, output: &mut RgbaImage, tile_rows: usize) {
+        // Without binarizing, faint anti-aliased edge pixels are kept when they sit next
+        // to a stroke, so only fully transparent pixels are skipped outright
+        let min_alpha = if self.config.binarize_alpha {
+            self.config.alpha_threshold
+        } else {
+            1
+        };
+
+        let row_len = rgba.width() as usize * 4;
+        if row_len == 0 {
+            return;
+        }
+        let clean_tile = |(tile, rows): (usize, &mut [u8])| {
+            for (i, row) in rows.chunks_exact_mut(row_len).enumerate() {
+                self.clean_row(rgba, (tile * tile_rows + i) as u32, row, min_alpha);
+            }
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            output
+                .par_chunks_mut(row_len * tile_rows)
+                .enumerate()
+                .for_each(clean_tile);
+        }
+        #[cfg(not(feature = "parallel"))]
+        output
+            .chunks_mut(row_len * tile_rows)
+            .enumerate()
+            .for_each(clean_tile);
+    }
 
-        // Create output buffer
-        let mut output: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::new(width, height);
+    /// Clean row `y` of `rgba` into `row`, the same row of the output.
+    ///
+    /// A pixel is considered isolated if it has fewer than `min_neighbors`
+    /// non-transparent neighbors
+    fn clean_row(&self, rgba: &RgbaImage, y: u32, row: &mut [u8], min_alpha: u8) {
+        let (width, height) = rgba.dimensions();
+        let four_connected = self.config.connectivity == Connectivity::Four;
 
-        // Simple cleanup: remove isolated pixels (noise)
-        // A pixel is considered isolated if it has fewer than 2 non-transparent neighbors
-        for y in 0..height {
-            for x in 0..width {
-                let pixel = rgba.get_pixel(x, y);
+        for (x, out) in (0..width).zip(row.chunks_exact_mut(4)) {
+            let pixel = rgba.get_pixel(x, y);
 
-                // Skip transparent pixels
-                if pixel[3] < 128 {
-                    output.put_pixel(x, y, Rgba([0, 0, 0, 0]));
-                    continue;
-                }
+            // Skip transparent pixels
+            if pixel[3] < min_alpha {
+                out.copy_from_slice(&[0, 0, 0, 0]);
+                continue;
+            }
 
-                // Count non-transparent neighbors
-                let mut neighbor_count = 0;
-                for dy in -1i32..=1 {
-                    for dx in -1i32..=1 {
-                        if dx == 0 && dy == 0 {
-                            continue;
-                        }
+            // Count non-transparent neighbors
+            let mut neighbor_count = 0;
+            for dy in -1i32..=1 {
+                for dx in -1i32..=1 {
+                    if (dx == 0 && dy == 0) || (four_connected && dx != 0 && dy != 0) {
+                        continue;
+                    }
 
-                        let nx = x as i32 + dx;
-                        let ny = y as i32 + dy;
+                    let nx = x as i32 + dx;
+                    let ny = y as i32 + dy;
 
-                        if nx >= 0 && nx < width as i32 && ny >= 0 && ny < height as i32 {
-                            let neighbor = rgba.get_pixel(nx as u32, ny as u32);
-                            if neighbor[3] >= 128 {
-                                neighbor_count += 1;
-                            }
+                    if nx >= 0 && nx < width as i32 && ny >= 0 && ny < height as i32 {
+                        let neighbor = rgba.get_pixel(nx as u32, ny as u32);
+                        if neighbor[3] >= self.config.alpha_threshold {
+                            neighbor_count += 1;
                         }
                     }
                 }
-
-                // Keep pixel if it has enough neighbors (not isolated noise)
-                if neighbor_count >= 2 {
-                    output.put_pixel(x, y, *pixel);
-                } else {
-                    output.put_pixel(x, y, Rgba([0, 0, 0, 0]));
-                }
             }
-        }
 
-        // Clean alpha channel: make pixels either fully transparent or fully opaque
-        for pixel in output.pixels_mut() {
-            if pixel[3] < 128 {
-                *pixel = Rgba([0, 0, 0, 0]);
+            // Keep pixel if it has enough neighbors (not isolated noise)
+            if neighbor_count >= self.config.min_neighbors {
+                out.copy_from_slice(&pixel.0);
             } else {
-                pixel[3] = 255;
+                out.copy_from_slice(&[0, 0, 0, 0]);
             }
         }
-
-        DynamicImage::ImageRgba8(output)
     }
 
     /// Get the original dimensions before normalization (for reverse mapping)
@@ -189,6 +282,218 @@ pub struct PaddingInfo {
     pub scale: f32,
 }
 
+/// Scale an image to fit `width`x`height` with its aspect ratio preserved, centered on
+/// padding (transparent, or the paper tone for grayscale frames)
+pub fn letterbox(img: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let (orig_width, orig_height) = img.dimensions();
+
+    // Calculate scale to fit within the canvas while preserving aspect ratio
+    let scale = (width as f32 / orig_width as f32).min(height as f32 / orig_height as f32);
+    let new_width = ((orig_width as f32 * scale).round() as u32).clamp(1, width);
+    let new_height = ((orig_height as f32 * scale).round() as u32).clamp(1, height);
+
+    log::debug!(
+        "Resizing {}x{} -> {}x{} (canvas {}x{})",
+        orig_width,
+        orig_height,
+        new_width,
+        new_height,
+        width,
+        height
+    );
+
+    // Resize with high-quality interpolation
+    let resized = img.resize_exact(new_width, new_height, FilterType::Lanczos3);
+
+    // Pad line art with transparency. Grayscale frames have no alpha to carry their
+    // content, so extend their paper instead and keep them opaque.
+    let padding = if is_grayscale(img) {
+        let paper = edge_luminance(img);
+        Rgba([paper, paper, paper, 255])
+    } else {
+        Rgba([0, 0, 0, 0])
+    };
+    let mut canvas: ImageBuffer<Rgba<u8>, Vec<u8>> =
+        ImageBuffer::from_pixel(width, height, padding);
+
+    // Center the resized image on the canvas
+    let x_offset = (width - new_width) / 2;
+    let y_offset = (height - new_height) / 2;
+    image::imageops::replace(
+        &mut canvas,
+        &resized.to_rgba8(),
+        i64::from(x_offset),
+        i64::from(y_offset),
+    );
+
+    DynamicImage::ImageRgba8(canvas)
+}
+
+/// Split a side-by-side reference into its left and right parts, cutting at `ratio` of
+/// the width (0.5 = down the middle)
+pub fn split_horizontal(img: &DynamicImage, ratio: f32) -> Result<(DynamicImage, DynamicImage)> {
+    let (width, height) = img.dimensions();
+    let split = (width as f32 * ratio.clamp(0.0, 1.0)).round() as u32;
+    if split == 0 || split >= width || height == 0 {
+        anyhow::bail!(
+            "Splitting a {}x{} image at {} would leave an empty half",
+            width,
+            height,
+            ratio
+        );
+    }
+
+    Ok((
+        img.crop_imm(0, 0, split, height),
+        img.crop_imm(split, 0, width - split, height),
+    ))
+}
+
+/// Luminance of a frame, keeping its alpha channel if it has one. Frames that are
+/// already grayscale are returned as they are.
+pub fn to_grayscale(img: DynamicImage) -> DynamicImage {
+    match img.color() {
+        ColorType::L8 | ColorType::La8 | ColorType::L16 | ColorType::La16 => img,
+        _ => img.grayscale(),
+    }
+}
+
+/// The generated frame's alpha with its color taken from the keyframes, for flat line
+/// art where the model's colors drift. Each pixel mixes the keyframes' colors at the
+/// same place (scaled if the sizes differ) `1 - t` to `t`, weighted by how much each
+/// keyframe has drawn there; where neither has, their average line colors are mixed.
+pub fn recolor_from_sources(
+    generated: &DynamicImage,
+    frame_a: &DynamicImage,
+    frame_b: &DynamicImage,
+    t: f32,
+) -> DynamicImage {
+    let t = t.clamp(0.0, 1.0);
+    let (rgba_a, rgba_b) = (frame_a.to_rgba8(), frame_b.to_rgba8());
+    let fallback = match (mean_line_color(&rgba_a), mean_line_color(&rgba_b)) {
+        (Some(color_a), Some(color_b)) => Some(mix(color_a, 1.0 - t, color_b, t)),
+        (Some(color), None) | (None, Some(color)) => Some(color),
+        (None, None) => None,
+    };
+
+    let mut recolored = generated.to_rgba8();
+    let (width, height) = recolored.dimensions();
+    for (x, y, pixel) in recolored.enumerate_pixels_mut() {
+        let source_a = sample_scaled(&rgba_a, x, y, width, height);
+        let source_b = sample_scaled(&rgba_b, x, y, width, height);
+        let weight_a = (1.0 - t) * f32::from(source_a[3]);
+        let weight_b = t * f32::from(source_b[3]);
+        let color = if weight_a + weight_b > 0.0 {
+            Some(mix(rgb(source_a), weight_a, rgb(source_b), weight_b))
+        } else {
+            fallback
+        };
+        if let Some(color) = color {
+            for (channel, value) in color.into_iter().enumerate() {
+                pixel[channel] = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    DynamicImage::ImageRgba8(recolored)
+}
+
+/// The pixel of `source` at the place of (x, y) in a `width` x `height` frame
+fn sample_scaled(source: &RgbaImage, x: u32, y: u32, width: u32, height: u32) -> Rgba<u8> {
+    let sx = u64::from(x) * u64::from(source.width()) / u64::from(width);
+    let sy = u64::from(y) * u64::from(source.height()) / u64::from(height);
+    *source.get_pixel(sx as u32, sy as u32)
+}
+
+fn rgb(pixel: Rgba<u8>) -> [f32; 3] {
+    [pixel[0], pixel[1], pixel[2]].map(f32::from)
+}
+
+/// Weighted mean of two colors; the weights must not both be zero
+fn mix(a: [f32; 3], weight_a: f32, b: [f32; 3], weight_b: f32) -> [f32; 3] {
+    let total = weight_a + weight_b;
+    [0, 1, 2].map(|channel| (a[channel] * weight_a + b[channel] * weight_b) / total)
+}
+
+/// Alpha-weighted mean color of the drawing, or None if nothing is drawn
+fn mean_line_color(rgba: &RgbaImage) -> Option<[f32; 3]> {
+    let mut sum = [0.0f64; 3];
+    let mut total = 0.0f64;
+    for pixel in rgba.pixels() {
+        let alpha = f64::from(pixel[3]);
+        for (channel, value) in sum.iter_mut().enumerate() {
+            *value += f64::from(pixel[channel]) * alpha;
+        }
+        total += alpha;
+    }
+    (total > 0.0).then(|| sum.map(|value| (value / total) as f32))
+}
+
+/// Slice a grid of panels (e.g. a 2x2 storyboard sheet) into its cells, left to right
+/// then top to bottom. Cell sizes are floored, so a remainder on the right or bottom
+/// edge is dropped.
+pub fn split_grid(img: &DynamicImage, rows: u32, cols: u32) -> Result<Vec<DynamicImage>> {
+    let (width, height) = img.dimensions();
+    let cell_width = width.checked_div(cols).unwrap_or(0);
+    let cell_height = height.checked_div(rows).unwrap_or(0);
+    if cell_width == 0 || cell_height == 0 {
+        anyhow::bail!(
+            "Splitting a {}x{} image into a {}x{} grid would leave empty cells",
+            width,
+            height,
+            rows,
+            cols
+        );
+    }
+
+    Ok((0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .map(|(row, col)| {
+            img.crop_imm(col * cell_width, row * cell_height, cell_width, cell_height)
+        })
+        .collect())
+}
+
+/// Mean color of an image's four corner pixels
+fn corner_color(rgba: &RgbaImage) -> [u8; 3] {
+    let (width, height) = rgba.dimensions();
+    if width == 0 || height == 0 {
+        return [255, 255, 255];
+    }
+
+    let corners = [
+        (0, 0),
+        (width - 1, 0),
+        (0, height - 1),
+        (width - 1, height - 1),
+    ];
+    let mut color = [0u8; 3];
+    for (c, channel) in color.iter_mut().enumerate() {
+        let total: u32 = corners
+            .iter()
+            .map(|&(x, y)| u32::from(rgba.get_pixel(x, y)[c]))
+            .sum();
+        *channel = (total / 4) as u8;
+    }
+    color
+}
+
+/// Mean luminance of the pixels along an image's border
+fn edge_luminance(img: &DynamicImage) -> u8 {
+    let luma = img.to_luma8();
+    let (width, height) = luma.dimensions();
+    let (total, count) = luma
+        .enumerate_pixels()
+        .filter(|(x, y, _)| *x == 0 || *y == 0 || *x == width - 1 || *y == height - 1)
+        .fold((0u64, 0u64), |(total, count), (_, _, pixel)| {
+            (total + u64::from(pixel[0]), count + 1)
+        });
+
+    if count == 0 {
+        return 255;
+    }
+    (total / count) as u8
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,6 +504,12 @@ mod tests {
             target_resolution: 512,
             normalize_resolution: true,
             min_stroke_length: 5.0,
+            binarize_alpha: true,
+            feather_radius: 0,
+            remove_background: None,
+            min_neighbors: 2,
+            connectivity: Connectivity::Eight,
+            alpha_threshold: 128,
         }
     }
 
@@ -244,4 +555,258 @@ mod tests {
         assert_eq!(restored.width(), original_width);
         assert_eq!(restored.height(), original_height);
     }
+
+    #[test]
+    fn test_cleanup_keeps_antialiased_alpha() {
+        // Solid 4x4 block with a soft edge column and one faint speck far away
+        let mut img = ImageBuffer::from_pixel(12, 12, Rgba([0, 0, 0, 0]));
+        for y in 2..6 {
+            for x in 2..6 {
+                img.put_pixel(x, y, Rgba([10, 10, 10, 255]));
+            }
+            img.put_pixel(6, y, Rgba([10, 10, 10, 90]));
+            img.put_pixel(1, y, Rgba([10, 10, 10, 200]));
+        }
+        img.put_pixel(10, 10, Rgba([10, 10, 10, 90]));
+        let img = DynamicImage::ImageRgba8(img);
+
+        let mut config = test_config();
+        config.binarize_alpha = false;
+        let soft = Preprocessor::new(&config).cleanup(&img).to_rgba8();
+        assert_eq!(soft.get_pixel(6, 3)[3], 90);
+        assert_eq!(soft.get_pixel(1, 3)[3], 200);
+        assert_eq!(soft.get_pixel(10, 10)[3], 0);
+
+        // Default behavior still snaps alpha
+        let hard = Preprocessor::new(&test_config()).cleanup(&img).to_rgba8();
+        assert_eq!(hard.get_pixel(6, 3)[3], 0);
+        assert_eq!(hard.get_pixel(1, 3)[3], 255);
+    }
+
+    #[test]
+    fn test_grayscale_padding_stays_opaque() {
+        let preprocessor = Preprocessor::new(&test_config());
+
+        let blank =
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(400, 200, image::Luma([90])));
+        let processed = preprocessor.process(&blank).unwrap();
+        assert!(is_grayscale(&processed));
+        assert_eq!(processed.to_rgba8().get_pixel(0, 0).0, [90, 90, 90, 255]);
+
+        // Line art is still padded with transparency
+        let processed = preprocessor
+            .process(&DynamicImage::new_rgba8(400, 200))
+            .unwrap();
+        assert_eq!(processed.to_rgba8().get_pixel(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_split_horizontal() {
+        let mut img = image::RgbaImage::new(200, 100);
+        img.put_pixel(150, 50, Rgba([255, 0, 0, 255]));
+        let img = DynamicImage::ImageRgba8(img);
+
+        let (left, right) = split_horizontal(&img, 0.5).unwrap();
+        assert_eq!(left.dimensions(), (100, 100));
+        assert_eq!(right.dimensions(), (100, 100));
+        assert_eq!(right.to_rgba8().get_pixel(50, 50).0, [255, 0, 0, 255]);
+
+        let (left, right) = split_horizontal(&img, 0.25).unwrap();
+        assert_eq!((left.width(), right.width()), (50, 150));
+
+        assert!(split_horizontal(&img, 0.0).is_err());
+        assert!(split_horizontal(&img, 1.0).is_err());
+        assert!(split_horizontal(&DynamicImage::new_rgba8(1, 10), 0.5).is_err());
+    }
+
+    #[test]
+    fn test_split_grid() {
+        let mut img = image::RgbaImage::new(200, 200);
+        img.put_pixel(150, 50, Rgba([255, 0, 0, 255]));
+        img.put_pixel(50, 150, Rgba([0, 255, 0, 255]));
+        let img = DynamicImage::ImageRgba8(img);
+
+        let cells = split_grid(&img, 2, 2).unwrap();
+        assert_eq!(cells.len(), 4);
+        assert!(cells.iter().all(|cell| cell.dimensions() == (100, 100)));
+        // Row-major: top-right is second, bottom-left third
+        assert_eq!(cells[1].to_rgba8().get_pixel(50, 50).0, [255, 0, 0, 255]);
+        assert_eq!(cells[2].to_rgba8().get_pixel(50, 50).0, [0, 255, 0, 255]);
+
+        let cells = split_grid(&DynamicImage::new_rgba8(201, 199), 2, 2).unwrap();
+        assert!(cells.iter().all(|cell| cell.dimensions() == (100, 99)));
+
+        assert!(split_grid(&img, 0, 2).is_err());
+        assert!(split_grid(&DynamicImage::new_rgba8(1, 10), 1, 2).is_err());
+    }
+
+    #[test]
+    fn test_feathered_seam_fades_gradually() {
+        let mut config = test_config();
+        config.target_resolution = 16;
+        let wide =
+            DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(8, 4, Rgba([200, 0, 0, 255])));
+        // Content lands in rows 4-11; rows 0-3 and 12-15 are padding
+        let column = |preprocessor: &Preprocessor| {
+            let processed = preprocessor.process(&wide).unwrap().to_rgba8();
+            (0..16)
+                .map(|y| processed.get_pixel(8, y)[3])
+                .collect::<Vec<_>>()
+        };
+
+        let hard = column(&Preprocessor::new(&config));
+        assert_eq!(&hard[3..6], &[0, 255, 255]);
+
+        config.feather_radius = 3;
+        let feathered = column(&Preprocessor::new(&config));
+        assert_eq!(&feathered[3..8], &[0, 64, 128, 191, 255]);
+        assert_eq!(&feathered[8..13], &[255, 191, 128, 64, 0]);
+    }
+
+    #[test]
+    fn test_white_background_keyed_to_transparency() {
+        // Black line on off-white paper, no alpha
+        let scan = DynamicImage::ImageRgb8(image::RgbImage::from_fn(32, 32, |x, _| {
+            if (14..18).contains(&x) {
+                image::Rgb([20, 20, 20])
+            } else {
+                image::Rgb([245, 242, 236])
+            }
+        }));
+        let preprocessor = Preprocessor::new(&test_config());
+
+        let keyed = preprocessor
+            .remove_background(&scan, &BackgroundKey::default())
+            .to_rgba8();
+        assert_eq!(keyed.get_pixel(0, 0)[3], 0);
+        assert_eq!(keyed.get_pixel(31, 20)[3], 0);
+        assert_eq!(keyed.get_pixel(15, 10).0, [20, 20, 20, 255]);
+
+        // An explicit color that doesn't match the paper leaves it alone
+        let key = BackgroundKey {
+            color: KeyColor::Rgb([0, 0, 255]),
+            tolerance: 24,
+        };
+        let untouched = preprocessor.remove_background(&scan, &key).to_rgba8();
+        assert_eq!(untouched.get_pixel(0, 0)[3], 255);
+
+        assert_eq!(
+            KeyColor::try_from("#F5F2EC".to_string()),
+            Ok(KeyColor::Rgb([245, 242, 236]))
+        );
+        assert!(KeyColor::try_from("white".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_min_neighbors_keeps_thin_line_ends() {
+        // A 1px line from x = 10 to 19; each end has a single neighbor
+        let line = DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, y| {
+            if y == 16 && (10..20).contains(&x) {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        }));
+        let span = |config: PreprocessingConfig| {
+            let cleaned = Preprocessor::new(&config).cleanup(&line).to_rgba8();
+            let kept: Vec<_> = (0..32)
+                .filter(|&x| cleaned.get_pixel(x, 16)[3] > 0)
+                .collect();
+            (kept.first().copied(), kept.last().copied())
+        };
+
+        assert_eq!(span(test_config()), (Some(11), Some(18)));
+        let thin = PreprocessingConfig {
+            min_neighbors: 1,
+            ..test_config()
+        };
+        assert_eq!(span(thin), (Some(10), Some(19)));
+
+        // Diagonal neighbors don't count with 4-connectivity
+        let diagonal = DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, y| {
+            Rgba([0, 0, 0, if x == y { 255 } else { 0 }])
+        }));
+        let four = PreprocessingConfig {
+            min_neighbors: 1,
+            connectivity: Connectivity::Four,
+            ..test_config()
+        };
+        let cleaned = Preprocessor::new(&four).cleanup(&diagonal).to_rgba8();
+        assert!(cleaned.pixels().all(|pixel| pixel[3] == 0));
+    }
+
+    #[test]
+    fn test_tiled_cleanup_matches_serial() {
+        // Noise and strokes, with a height that doesn't divide into whole tiles
+        let mut seed = 12345u32;
+        let noisy = RgbaImage::from_fn(97, 61, |x, y| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let stroke = x % 13 < 2 || y % 17 == 0;
+            let alpha = if stroke || (seed >> 16) % 7 == 0 {
+                (seed >> 8) as u8 | 1
+            } else {
+                0
+            };
+            Rgba([(seed >> 4) as u8, x as u8, y as u8, alpha])
+        });
+
+        for config in [
+            test_config(),
+            PreprocessingConfig {
+                binarize_alpha: false,
+                connectivity: Connectivity::Four,
+                ..test_config()
+            },
+        ] {
+            let preprocessor = Preprocessor::new(&config);
+            let cleaned = |tile_rows: usize| {
+                let mut output = RgbaImage::new(97, 61);
+                preprocessor.remove_isolated_pixels(&noisy, &mut output, tile_rows);
+                output
+            };
+
+            // One tile covering the whole image is the serial pass
+            let serial = cleaned(61);
+            assert!(serial.pixels().any(|p| p[3] > 0));
+            assert!(serial != noisy);
+            for tile_rows in [1, 8, CLEANUP_TILE_ROWS] {
+                assert!(cleaned(tile_rows) == serial, "{tile_rows} rows per tile");
+            }
+        }
+    }
+
+    #[test]
+    fn test_recolor_keeps_generated_alpha() {
+        // Keyframes in two flat colors; the generated frame drifted to green and has a
+        // shape of its own
+        let frame_a = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 8, Rgba([200, 0, 0, 255])));
+        let frame_b = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 8, Rgba([0, 0, 100, 255])));
+        let generated = DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, _| {
+            Rgba([0, 255, 0, if x < 4 { 255 } else { 40 }])
+        }));
+
+        let recolored = recolor_from_sources(&generated, &frame_a, &frame_b, 0.25).to_rgba8();
+        for (x, _, pixel) in recolored.enumerate_pixels() {
+            assert_eq!(pixel.0, [150, 0, 25, if x < 4 { 255 } else { 40 }]);
+        }
+
+        // Where neither keyframe is drawn, their overall line colors are mixed
+        let sparse = |color: Rgba<u8>| {
+            DynamicImage::ImageRgba8(RgbaImage::from_fn(8, 8, |x, y| {
+                if (x, y) == (0, 0) {
+                    color
+                } else {
+                    Rgba([0, 0, 0, 0])
+                }
+            }))
+        };
+        let recolored = recolor_from_sources(
+            &generated,
+            &sparse(Rgba([200, 0, 0, 255])),
+            &sparse(Rgba([0, 0, 100, 255])),
+            0.5,
+        )
+        .to_rgba8();
+        assert_eq!(recolored.get_pixel(5, 5).0, [100, 0, 50, 40]);
+    }
 }
@@ -0,0 +1,96 @@
+use image::{imageops::FilterType, DynamicImage};
+
+/// Perceptual hash (dHash) of an image.
+///
+/// The image is composited over white, shrunk to 9x8 and each bit records whether a
+/// pixel is brighter than its right-hand neighbour, so small rendering differences
+/// barely change the hash while a different pose changes many bits.
+pub fn phash(img: &DynamicImage) -> u64 {
+    let small = img.resize_exact(9, 8, FilterType::Triangle).to_rgba8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = luminance_over_white(small.get_pixel(x, y).0);
+            let right = luminance_over_white(small.get_pixel(x + 1, y).0);
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+
+    hash
+}
+
+/// Number of differing bits between two hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Whether a frame is within `max_distance` of either keyframe's hash
+pub fn is_near_duplicate(frame: &DynamicImage, keyframe_hashes: &[u64], max_distance: u32) -> bool {
+    let hash = phash(frame);
+    keyframe_hashes
+        .iter()
+        .any(|&keyframe| hamming_distance(hash, keyframe) <= max_distance)
+}
+
+/// For each frame, whether it is within `max_distance` of the last frame before it that
+/// wasn't itself a repeat, so a run of identical frames marks all but its first
+pub fn consecutive_repeats(frames: &[DynamicImage], max_distance: u32) -> Vec<bool> {
+    let mut previous: Option<u64> = None;
+    frames
+        .iter()
+        .map(|frame| {
+            let hash = phash(frame);
+            let repeat =
+                previous.is_some_and(|previous| hamming_distance(hash, previous) <= max_distance);
+            if !repeat {
+                previous = Some(hash);
+            }
+            repeat
+        })
+        .collect()
+}
+
+fn luminance_over_white([r, g, b, a]: [u8; 4]) -> f32 {
+    let alpha = f32::from(a) / 255.0;
+    let luma = 0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b);
+    luma * alpha + 255.0 * (1.0 - alpha)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    /// A black stroke on a transparent background, spanning columns `x0..x1`
+    fn stroke_frame(x0: u32, x1: u32) -> DynamicImage {
+        let mut img = RgbaImage::new(64, 64);
+        for y in 8..56 {
+            for x in x0..x1 {
+                img.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+            }
+        }
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn test_copied_keyframe_is_near_duplicate() {
+        let key_a = stroke_frame(4, 12);
+        let key_b = stroke_frame(52, 60);
+        let hashes = [phash(&key_a), phash(&key_b)];
+
+        // Model "cheated" and returned frame A
+        assert!(is_near_duplicate(&key_a, &hashes, 4));
+
+        // A genuine inbetween with the stroke halfway across
+        let inbetween = stroke_frame(28, 36);
+        assert!(!is_near_duplicate(&inbetween, &hashes, 4));
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert_eq!(hamming_distance(u64::MAX, 0), 64);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::confidence::{rgba_stats, ImageStats, DEFAULT_ALPHA_THRESHOLD};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Memoizes per-image work the heuristics repeat (the RGBA conversion and
+/// [`ImageStats`]) keyed by a hash of the image's pixels, so a keyframe held across
+/// several pairs in a batch is only measured once. Holds at most `capacity` images,
+/// evicting the least recently used; a capacity of 0 disables caching.
+pub struct ImageStatsCache {
+    capacity: usize,
+    /// Alpha at or above which a pixel counts toward the stats
+    alpha_threshold: u8,
+    /// Least recently used first
+    entries: Mutex<Vec<CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct CacheEntry {
+    key: u64,
+    rgba: Arc<RgbaImage>,
+    /// Stats per sample size asked for
+    stats: Vec<(usize, ImageStats)>,
+}
+
+impl ImageStatsCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            alpha_threshold: DEFAULT_ALPHA_THRESHOLD,
+            entries: Mutex::new(Vec::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Measure stats over pixels with alpha at or above `threshold`
+    #[must_use]
+    pub fn with_alpha_threshold(mut self, threshold: u8) -> Self {
+        self.alpha_threshold = threshold;
+        self
+    }
+
+    /// The image's pixels as RGBA
+    pub fn rgba(&self, img: &DynamicImage) -> Arc<RgbaImage> {
+        let key = content_key(img);
+        if let Some(rgba) = self.lookup(key, |entry| Arc::clone(&entry.rgba)) {
+            return rgba;
+        }
+        let rgba = Arc::new(img.to_rgba8());
+        self.insert(key, &rgba, None);
+        rgba
+    }
+
+    /// Brightness and saturation, sampling about `samples` pixels
+    pub fn stats(&self, img: &DynamicImage, samples: usize) -> ImageStats {
+        let key = content_key(img);
+        let cached = self.lookup(key, |entry| {
+            let stats = entry.stats.iter().find(|(n, _)| *n == samples);
+            (stats.map(|(_, stats)| *stats), Arc::clone(&entry.rgba))
+        });
+        let rgba = match cached {
+            Some((Some(stats), _)) => return stats,
+            Some((None, rgba)) => rgba,
+            None => Arc::new(img.to_rgba8()),
+        };
+        let stats = rgba_stats(&rgba, samples, self.alpha_threshold);
+        self.insert(key, &rgba, Some((samples, stats)));
+        stats
+    }
+
+    /// Lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Lookups that had to convert the image
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Read the entry for `key`, marking it most recently used. The lock is only held
+    /// for the lookup; the slow conversions and stats happen outside it.
+    fn lookup<R>(&self, key: u64, read: impl FnOnce(&CacheEntry) -> R) -> Option<R> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(pos) = entries.iter().position(|entry| entry.key == key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        let entry = entries.remove(pos);
+        let result = read(&entry);
+        entries.push(entry);
+        Some(result)
+    }
+
+    /// Add what was computed for `key`, merging with an entry another thread may have
+    /// added in the meantime
+    fn insert(&self, key: u64, rgba: &Arc<RgbaImage>, stats: Option<(usize, ImageStats)>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut entry = if let Some(pos) = entries.iter().position(|entry| entry.key == key) {
+            entries.remove(pos)
+        } else {
+            if entries.len() >= self.capacity {
+                entries.remove(0);
+            }
+            CacheEntry {
+                key,
+                rgba: Arc::clone(rgba),
+                stats: Vec::new(),
+            }
+        };
+        if let Some((samples, stats)) = stats {
+            if !entry.stats.iter().any(|(n, _)| *n == samples) {
+                entry.stats.push((samples, stats));
+            }
+        }
+        entries.push(entry);
+    }
+}
+
+/// Hash of an image's dimensions, pixel format and pixels. This reads every pixel, so
+/// it is computed before taking the cache lock.
+fn content_key(img: &DynamicImage) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    img.dimensions().hash(&mut hasher);
+    img.color().hash(&mut hasher);
+    img.as_bytes().hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let cache = ImageStatsCache::new(2);
+        let image = |value: u8| {
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, image::Rgba([value, 0, 0, 255])))
+        };
+
+        cache.stats(&image(1), 100);
+        cache.stats(&image(2), 100);
+        cache.stats(&image(1), 100);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+
+        // 2 is now the least recently used, so adding 3 evicts it
+        cache.stats(&image(3), 100);
+        cache.stats(&image(1), 100);
+        cache.stats(&image(2), 100);
+        assert_eq!((cache.hits(), cache.misses()), (2, 4));
+
+        let uncached = ImageStatsCache::new(0);
+        uncached.rgba(&image(1));
+        uncached.rgba(&image(1));
+        assert_eq!((uncached.hits(), uncached.misses()), (0, 2));
+    }
+
+    #[test]
+    fn test_new_sample_size_reuses_cached_pixels() {
+        let cache = ImageStatsCache::new(4);
+        let img =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, image::Rgba([9, 0, 0, 255])));
+
+        let full = cache.stats(&img, 100);
+        cache.stats(&img, 8);
+        assert!((cache.stats(&img, 100).brightness - full.brightness).abs() < f32::EPSILON);
+        cache.rgba(&img);
+        assert_eq!((cache.hits(), cache.misses()), (3, 1));
+
+        let entries = cache.entries.lock().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].stats.len(), 2);
+    }
+}
@@ -0,0 +1,106 @@
+//! Helpers shared by unit tests across modules
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::Mutex;
+use std::thread;
+
+/// Serve one canned HTTP response per connection on a local port, returning the base URL.
+///
+/// Once the responses run out the listener closes, so further requests fail to connect.
+pub fn serve(responses: Vec<Vec<u8>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for response in responses {
+            let Ok((mut stream, _)) = listener.accept() else {
+                return;
+            };
+
+            read_request(&mut stream);
+            let _ = stream.write_all(&response);
+        }
+    });
+
+    format!("http://{addr}")
+}
+
+/// Build a complete HTTP/1.1 response
+pub fn http_response(status: &str, content_type: &str, body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+/// Read a request's headers and body, so closing the socket doesn't reset the connection
+fn read_request(stream: &mut impl Read) -> Vec<u8> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    let header_end = loop {
+        if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return request,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    };
+
+    let headers = String::from_utf8_lossy(&request[..header_end]).to_ascii_lowercase();
+    let content_length = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while request.len() < header_end + content_length {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    request
+}
+
+/// Records warnings so tests can check what was logged
+struct CaptureLogger(Mutex<Vec<String>>);
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Warn
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURED: CaptureLogger = CaptureLogger(Mutex::new(Vec::new()));
+
+/// Route warnings into a buffer shared by every test in the binary (a process can
+/// only install one logger)
+pub fn capture_warnings() {
+    let _ = log::set_logger(&CAPTURED);
+    log::set_max_level(log::LevelFilter::Warn);
+}
+
+/// Whether any warning captured so far contains `text`
+pub fn warned(text: &str) -> bool {
+    CAPTURED
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|msg| msg.contains(text))
+}